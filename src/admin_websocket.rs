@@ -1,15 +1,21 @@
+use crate::cap_grant::CapGrantBuilder;
 use crate::error::{ConductorApiError, ConductorApiResult};
+use crate::signing::{client_signing::ClientAgentSigner, seed_derivation::SigningKeySeed};
 use crate::util::AbortOnDropHandle;
+use crate::{AppWebsocket, DynAgentSigner};
 use holo_hash::DnaHash;
 use holochain_conductor_api::{
     AdminRequest, AdminResponse, AppAuthenticationToken, AppAuthenticationTokenIssued, AppInfo,
-    AppInterfaceInfo, AppStatusFilter, FullStateDump, IssueAppAuthenticationTokenPayload,
+    AppInterfaceInfo, AppStatusFilter, CellInfo, FullStateDump, IssueAppAuthenticationTokenPayload,
     RevokeAgentKeyPayload, StorageInfo,
 };
 use holochain_types::websocket::AllowedOrigins;
 use holochain_types::{
+    app::{AppBundleSource, InstalledAppId, RoleSettingsMap},
     dna::AgentPubKey,
-    prelude::{CellId, DeleteCloneCellPayload, InstallAppPayload, UpdateCoordinatorsPayload},
+    prelude::{
+        CellId, DeleteCloneCellPayload, InstallAppPayload, NetworkSeed, UpdateCoordinatorsPayload,
+    },
 };
 use holochain_websocket::{connect, ConnectRequest, WebsocketConfig, WebsocketSender};
 use holochain_zome_types::{
@@ -18,7 +24,10 @@ use holochain_zome_types::{
 };
 use serde::{Deserialize, Serialize};
 use std::fmt::Formatter;
-use std::{net::ToSocketAddrs, sync::Arc};
+use std::{
+    net::{Ipv4Addr, SocketAddr, ToSocketAddrs},
+    sync::Arc,
+};
 
 /// A websocket connection to the Holochain Conductor admin interface.
 #[derive(Clone)]
@@ -45,6 +54,96 @@ pub struct AuthorizeSigningCredentialsPayload {
     pub functions: Option<GrantedFunctions>,
 }
 
+/// Options for [AdminWebsocket::install_and_connect].
+#[derive(Debug)]
+pub struct InstallAndConnectOptions {
+    /// The id to install the app with. Defaults to the name in the app manifest.
+    pub installed_app_id: Option<InstalledAppId>,
+    /// The agent to install the app for. A new agent key is generated if not set.
+    pub agent_key: Option<AgentPubKey>,
+    pub network_seed: Option<NetworkSeed>,
+    pub roles_settings: Option<RoleSettingsMap>,
+    /// The origins that the new app interface accepts connections from.
+    pub allowed_origins: AllowedOrigins,
+    /// The origin header to connect to the app interface with, which must be allowed by
+    /// `allowed_origins`.
+    pub origin: Option<String>,
+    /// The functions to authorize signing credentials for. Defaults to all functions.
+    pub functions: Option<GrantedFunctions>,
+}
+
+impl Default for InstallAndConnectOptions {
+    fn default() -> Self {
+        Self {
+            installed_app_id: None,
+            agent_key: None,
+            network_seed: None,
+            roles_settings: None,
+            allowed_origins: AllowedOrigins::Any,
+            origin: None,
+            functions: None,
+        }
+    }
+}
+
+/// The payload for [AdminWebsocket::install_web_app].
+#[cfg(feature = "web_app")]
+#[derive(Debug)]
+pub struct InstallWebAppPayload {
+    /// The encoded web app bundle, as found in a `.webhapp` file.
+    pub bundle: Vec<u8>,
+    /// The directory to extract the UI into, which must not exist or be empty.
+    pub ui_dir: std::path::PathBuf,
+    pub agent_key: Option<AgentPubKey>,
+    pub installed_app_id: Option<InstalledAppId>,
+    pub network_seed: Option<NetworkSeed>,
+    pub roles_settings: Option<RoleSettingsMap>,
+}
+
+/// A web app that has been installed with [AdminWebsocket::install_web_app].
+#[cfg(feature = "web_app")]
+#[derive(Clone, Debug)]
+pub struct InstalledWebApp {
+    pub app_info: AppInfo,
+    /// The directory that the UI was extracted into.
+    pub ui_dir: std::path::PathBuf,
+}
+
+/// Options for [AdminWebsocket::connect_app].
+#[derive(Clone, Debug)]
+pub struct ConnectAppOptions {
+    /// The origins to accept connections from, if a new app interface has to be attached.
+    pub allowed_origins: AllowedOrigins,
+    /// The origin header to connect to the app interface with.
+    pub origin: Option<String>,
+    /// Whether to authorize signing credentials for every cell of the app and add them to the
+    /// signer.
+    pub authorize_signing_credentials: bool,
+    /// The functions to authorize signing credentials for. Defaults to all functions.
+    pub functions: Option<GrantedFunctions>,
+}
+
+impl Default for ConnectAppOptions {
+    fn default() -> Self {
+        Self {
+            allowed_origins: AllowedOrigins::Any,
+            origin: None,
+            authorize_signing_credentials: true,
+            functions: None,
+        }
+    }
+}
+
+/// An app that is installed and enabled, with a connection that is ready to make zome calls.
+#[derive(Clone, Debug)]
+pub struct ConnectedApp {
+    pub app_info: AppInfo,
+    pub app_ws: AppWebsocket,
+    /// The signer used by `app_ws`, holding signing credentials for every cell of the app if they
+    /// were authorized.
+    pub signer: ClientAgentSigner,
+}
+
 impl AdminWebsocket {
     /// Connect to a Conductor API admin websocket.
     ///
@@ -92,17 +191,24 @@ impl AdminWebsocket {
     /// let admin_ws = AdminWebsocket::connect_with_config((Ipv4Addr::LOCALHOST, 30_000), client_config).await.unwrap();
     /// # }
     /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
     pub async fn connect_with_config(
         socket_addr: impl ToSocketAddrs,
         websocket_config: Arc<WebsocketConfig>,
     ) -> ConductorApiResult<Self> {
         let mut last_err = None;
         for addr in socket_addr.to_socket_addrs()? {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(%addr, "Connecting to admin websocket");
+
             let request: ConnectRequest = addr.into();
 
             match Self::connect_with_request_and_config(request, websocket_config.clone()).await {
                 Ok(admin_ws) => return Ok(admin_ws),
                 Err(e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(%addr, error = %e, "Failed to connect to admin websocket");
+
                     last_err = Some(e);
                 }
             }
@@ -157,6 +263,7 @@ impl AdminWebsocket {
     /// }
     /// # }
     /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
     pub async fn connect_with_request_and_config(
         request: ConnectRequest,
         websocket_config: Arc<WebsocketConfig>,
@@ -360,6 +467,13 @@ impl AdminWebsocket {
         }
     }
 
+    /// Start building a capability grant for calling zome functions in the given cell.
+    ///
+    /// See [CapGrantBuilder] for the options.
+    pub fn build_cap_grant(&self, cell_id: CellId) -> CapGrantBuilder<'_> {
+        CapGrantBuilder::new(self, cell_id)
+    }
+
     pub async fn grant_zome_call_capability(
         &self,
         payload: GrantZomeCallCapabilityPayload,
@@ -505,13 +619,16 @@ impl AdminWebsocket {
         }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, err, fields(cell_id = ?request.cell_id))
+    )]
     pub async fn authorize_signing_credentials(
         &self,
         request: AuthorizeSigningCredentialsPayload,
     ) -> ConductorApiResult<crate::signing::client_signing::SigningCredentials> {
-        use holochain_zome_types::capability::{ZomeCallCapGrant, CAP_SECRET_BYTES};
+        use holochain_zome_types::capability::CAP_SECRET_BYTES;
         use rand::{rngs::OsRng, RngCore};
-        use std::collections::BTreeSet;
 
         let mut csprng = OsRng;
         let keypair = ed25519_dalek::SigningKey::generate(&mut csprng);
@@ -521,27 +638,456 @@ impl AdminWebsocket {
         let mut cap_secret = [0; CAP_SECRET_BYTES];
         csprng.fill_bytes(&mut cap_secret);
 
+        let credentials = crate::signing::client_signing::SigningCredentials {
+            signing_agent_key,
+            keypair,
+            cap_secret: cap_secret.into(),
+        };
+        self.grant_signing_credentials(
+            request,
+            &credentials.signing_agent_key,
+            credentials.cap_secret,
+        )
+        .await?;
+
+        Ok(credentials)
+    }
+
+    /// Derive signing credentials for a cell from a [SigningKeySeed] and grant them a capability.
+    ///
+    /// Works like [AdminWebsocket::authorize_signing_credentials], except that the signing key and
+    /// cap secret are derived from the seed, the cell id and the `index`, rather than generated
+    /// randomly. The same credentials can be derived again later with
+    /// [SigningKeySeed::derive_credentials].
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, err, fields(cell_id = ?request.cell_id, index))
+    )]
+    pub async fn authorize_signing_credentials_from_seed(
+        &self,
+        seed: &SigningKeySeed,
+        index: u32,
+        request: AuthorizeSigningCredentialsPayload,
+    ) -> ConductorApiResult<crate::signing::client_signing::SigningCredentials> {
+        let credentials = seed.derive_credentials(&request.cell_id, index);
+        self.grant_signing_credentials(
+            request,
+            &credentials.signing_agent_key,
+            credentials.cap_secret,
+        )
+        .await?;
+
+        Ok(credentials)
+    }
+
+    /// Authorize signing credentials for every provisioned and cloned cell of an app, and add them
+    /// to the given signer.
+    ///
+    /// Cells that the signer already has credentials for which cover the requested functions are
+    /// skipped. Returns the ids of the cells that new credentials were authorized for.
+    ///
+    /// To authorize cells lazily instead, including clone cells that are created later, use an
+    /// [AutoAuthorizingAgentSigner](crate::AutoAuthorizingAgentSigner).
+    pub async fn authorize_signing_credentials_for_app(
+        &self,
+        app_info: &AppInfo,
+        functions: Option<GrantedFunctions>,
+        signer: &ClientAgentSigner,
+    ) -> ConductorApiResult<Vec<CellId>> {
+        let granted_functions = functions.clone().unwrap_or(GrantedFunctions::All);
+        let cell_ids = app_info
+            .cell_info
+            .values()
+            .flatten()
+            .filter_map(|cell| match cell {
+                CellInfo::Provisioned(provisioned_cell) => Some(provisioned_cell.cell_id.clone()),
+                CellInfo::Cloned(cloned_cell) => Some(cloned_cell.cell_id.clone()),
+                _ => None,
+            })
+            .filter(|cell_id| !signer.has_credentials_for(cell_id, &granted_functions))
+            .collect::<Vec<_>>();
+
+        for cell_id in &cell_ids {
+            let credentials = self
+                .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+                    cell_id: cell_id.clone(),
+                    functions: functions.clone(),
+                })
+                .await?;
+            signer.add_scoped_credentials(cell_id.clone(), granted_functions.clone(), credentials);
+        }
+
+        Ok(cell_ids)
+    }
+
+    /// Install an app, enable it and connect to it, ready to make zome calls.
+    ///
+    /// A new app interface is attached for the app, and signing credentials are authorized for
+    /// each of its cells and added to the returned signer. The app interface is connected to on
+    /// localhost, where the conductor binds it.
+    ///
+    /// If any step after installing fails, the app is uninstalled again before the error is
+    /// returned. Cells that fail to start when the app is enabled are reported with
+    /// [ConductorApiError::EnableAppFailed]. The conductor has no request to detach an app
+    /// interface, so if connecting fails after the interface was attached, it stays attached until
+    /// the conductor restarts. It only accepts connections for the uninstalled app, so it can't be
+    /// used by another app.
+    ///
+    /// Nothing is rolled back if the returned future is cancelled, for example by a timeout, so the
+    /// app may be left installed and enabled.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// use std::net::Ipv4Addr;
+    /// use std::path::PathBuf;
+    /// use holochain_client::{AdminWebsocket, AppBundleSource, ExternIO};
+    ///
+    /// let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, 30_000)).await.unwrap();
+    /// let app = admin_ws
+    ///     .install_and_connect(
+    ///         AppBundleSource::Path(PathBuf::from("./my-app.happ")),
+    ///         Default::default(),
+    ///     )
+    ///     .await
+    ///     .unwrap();
+    ///
+    /// app.app_ws
+    ///     .call_zome("my_role".to_string().into(), "my_zome".into(), "my_fn".into(), ExternIO::encode(()).unwrap())
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub async fn install_and_connect(
+        &self,
+        source: AppBundleSource,
+        options: InstallAndConnectOptions,
+    ) -> ConductorApiResult<ConnectedApp> {
+        let app_info = self
+            .install_app(InstallAppPayload {
+                source,
+                agent_key: options.agent_key,
+                installed_app_id: options.installed_app_id,
+                network_seed: options.network_seed,
+                roles_settings: options.roles_settings,
+                ignore_genesis_failure: false,
+                allow_throwaway_random_agent_key: false,
+            })
+            .await?;
+        let installed_app_id = app_info.installed_app_id.clone();
+
+        let mut _attached_port = None;
+        let connect = async {
+            let enabled = self.enable_app(installed_app_id.clone()).await?;
+            if !enabled.errors.is_empty() {
+                return Err(ConductorApiError::EnableAppFailed(enabled.errors));
+            }
+
+            let signer = ClientAgentSigner::default();
+            self.authorize_signing_credentials_for_app(&enabled.app, options.functions, &signer)
+                .await?;
+
+            let port = self
+                .attach_app_interface(0, options.allowed_origins, Some(installed_app_id.clone()))
+                .await?;
+            _attached_port = Some(port);
+            let app_ws = self
+                .connect_app_websocket(
+                    installed_app_id.clone(),
+                    port,
+                    options.origin,
+                    signer.clone().into(),
+                )
+                .await?;
+
+            Ok(ConnectedApp {
+                app_info: enabled.app,
+                app_ws,
+                signer,
+            })
+        };
+
+        match connect.await {
+            Ok(connected) => Ok(connected),
+            Err(e) => {
+                if let Err(_uninstall_err) = self.uninstall_app(installed_app_id, false).await {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(error = %_uninstall_err, "Failed to uninstall app after error");
+                }
+                #[cfg(feature = "tracing")]
+                if let Some(port) = _attached_port {
+                    tracing::warn!(port, "App interface remains attached after error");
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Connect to an app that is already installed.
+    ///
+    /// An existing app interface is used if there is one that the app can use and that accepts
+    /// the origin in `options`. Otherwise a new app interface is attached for the app. The app
+    /// interface is connected to on localhost, where the conductor binds it.
+    ///
+    /// Signing credentials are authorized for every cell of the app and added to the returned
+    /// signer, unless `options.authorize_signing_credentials` is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// use std::net::Ipv4Addr;
+    /// use holochain_client::AdminWebsocket;
+    ///
+    /// let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, 30_000)).await.unwrap();
+    /// let app = admin_ws
+    ///     .connect_app("test-app".to_string(), Default::default())
+    ///     .await
+    ///     .unwrap();
+    /// # }
+    /// ```
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, err, fields(installed_app_id = %installed_app_id))
+    )]
+    pub async fn connect_app(
+        &self,
+        installed_app_id: InstalledAppId,
+        options: ConnectAppOptions,
+    ) -> ConductorApiResult<ConnectedApp> {
+        let existing_port = self
+            .list_app_interfaces()
+            .await?
+            .into_iter()
+            .find(|interface| {
+                let app_allowed = interface
+                    .installed_app_id
+                    .as_ref()
+                    .is_none_or(|id| *id == installed_app_id);
+                let origin_allowed = match &interface.allowed_origins {
+                    AllowedOrigins::Any => true,
+                    AllowedOrigins::Origins(origins) => options
+                        .origin
+                        .as_ref()
+                        .is_some_and(|origin| origins.contains(origin)),
+                };
+                app_allowed && origin_allowed
+            })
+            .map(|interface| interface.port);
+        let port = match existing_port {
+            Some(port) => port,
+            None => {
+                self.attach_app_interface(
+                    0,
+                    options.allowed_origins,
+                    Some(installed_app_id.clone()),
+                )
+                .await?
+            }
+        };
+
+        let signer = ClientAgentSigner::default();
+        let app_ws = self
+            .connect_app_websocket(
+                installed_app_id,
+                port,
+                options.origin,
+                signer.clone().into(),
+            )
+            .await?;
+        let app_info = app_ws.cached_app_info();
+        if options.authorize_signing_credentials {
+            self.authorize_signing_credentials_for_app(&app_info, options.functions, &signer)
+                .await?;
+        }
+
+        Ok(ConnectedApp {
+            app_info,
+            app_ws,
+            signer,
+        })
+    }
+
+    /// Install a web app, extracting its UI into a directory.
+    ///
+    /// The app bundle inside the web app bundle is installed with [AdminWebsocket::install_app].
+    /// The UI is extracted first, and is removed again if installing fails. UI files whose paths
+    /// would be written outside of the UI directory are rejected, as are UIs larger than 512 MiB
+    /// when extracted, and nothing is installed.
+    #[cfg(feature = "web_app")]
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub async fn install_web_app(
+        &self,
+        payload: InstallWebAppPayload,
+    ) -> ConductorApiResult<InstalledWebApp> {
+        use holochain_types::web_app::WebAppBundle;
+
+        let bundle = WebAppBundle::decode(&payload.bundle).map_err(|e| {
+            ConductorApiError::BundleError(format!("Failed to decode web app bundle: {e}"))
+        })?;
+        let app_bundle = bundle
+            .happ_bundle()
+            .await
+            .and_then(|app_bundle| app_bundle.encode())
+            .map_err(|e| {
+                ConductorApiError::BundleError(format!("Failed to read app bundle: {e}"))
+            })?;
+        let ui_zip = bundle
+            .web_ui_zip_bytes()
+            .await
+            .map_err(|e| ConductorApiError::BundleError(format!("Failed to read UI: {e}")))?;
+
+        let ui_zip = ui_zip.to_vec();
+        let ui_dir = payload.ui_dir.clone();
+        tokio::task::spawn_blocking(move || {
+            crate::bundle::web_app::extract_ui_zip(&ui_zip, &ui_dir)
+        })
+        .await
+        .map_err(|e| ConductorApiError::BundleError(format!("Failed to extract UI: {e}")))??;
+
+        let installed = self
+            .install_app(InstallAppPayload {
+                source: AppBundleSource::Bytes(app_bundle),
+                agent_key: payload.agent_key,
+                installed_app_id: payload.installed_app_id,
+                network_seed: payload.network_seed,
+                roles_settings: payload.roles_settings,
+                ignore_genesis_failure: false,
+                allow_throwaway_random_agent_key: false,
+            })
+            .await;
+        match installed {
+            Ok(app_info) => Ok(InstalledWebApp {
+                app_info,
+                ui_dir: payload.ui_dir,
+            }),
+            Err(e) => {
+                // The directory was empty before the UI was extracted into it
+                crate::bundle::web_app::remove_ui_dir(payload.ui_dir).await;
+                Err(e)
+            }
+        }
+    }
+
+    /// Issue a token for an app and connect to it on the app interface at the given port on
+    /// localhost.
+    async fn connect_app_websocket(
+        &self,
+        installed_app_id: InstalledAppId,
+        port: u16,
+        origin: Option<String>,
+        signer: DynAgentSigner,
+    ) -> ConductorApiResult<AppWebsocket> {
+        let issued = self
+            .issue_app_auth_token(IssueAppAuthenticationTokenPayload::for_installed_app_id(
+                installed_app_id,
+            ))
+            .await?;
+
+        let mut request: ConnectRequest = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), port).into();
+        if let Some(origin) = origin {
+            request = request.try_set_header("Origin", &origin)?;
+        }
+
+        AppWebsocket::connect_with_request_and_config(
+            request,
+            Arc::new(WebsocketConfig::CLIENT_DEFAULT),
+            issued.token,
+            signer,
+        )
+        .await
+    }
+
+    /// Create a signing key in Lair and grant it a capability to call zome functions.
+    ///
+    /// Works like [AdminWebsocket::authorize_signing_credentials], except that the private key is
+    /// created in a new seed in Lair rather than in the client process, and never leaves Lair.
+    /// Add the returned credentials to a [LairAgentSigner](crate::LairAgentSigner) connected to
+    /// the same Lair with
+    /// [LairAgentSigner::add_signing_credentials](crate::LairAgentSigner::add_signing_credentials).
+    #[cfg(feature = "lair_signing")]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, err, fields(cell_id = ?request.cell_id))
+    )]
+    pub async fn authorize_lair_signing_credentials(
+        &self,
+        lair_client: &lair_keystore_api::LairClient,
+        request: AuthorizeSigningCredentialsPayload,
+    ) -> ConductorApiResult<crate::signing::lair_signing::LairSigningCredentials> {
+        use holochain_zome_types::capability::CAP_SECRET_BYTES;
+        use rand::{rngs::OsRng, RngCore};
+
+        // Lair seed tags must be unique
+        let mut tag = [0; 16];
+        OsRng.fill_bytes(&mut tag);
+        let tag = tag
+            .iter()
+            .fold("zome-call-signing-key-".to_string(), |tag, b| {
+                tag + &format!("{b:02x}")
+            });
+
+        let seed_info = lair_client
+            .new_seed(tag.into(), None, false)
+            .await
+            .map_err(|e| {
+                ConductorApiError::KeystoreError(format!("Failed to create signing key: {e}"))
+            })?;
+        let signing_agent_key = AgentPubKey::from_raw_32(seed_info.ed25519_pub_key.0.to_vec());
+
+        let mut cap_secret = [0; CAP_SECRET_BYTES];
+        OsRng.fill_bytes(&mut cap_secret);
+
+        let credentials = crate::signing::lair_signing::LairSigningCredentials {
+            signing_agent_key,
+            cap_secret: cap_secret.into(),
+        };
+        self.grant_signing_credentials(
+            request,
+            &credentials.signing_agent_key,
+            credentials.cap_secret,
+        )
+        .await?;
+
+        Ok(credentials)
+    }
+
+    async fn grant_signing_credentials(
+        &self,
+        request: AuthorizeSigningCredentialsPayload,
+        signing_agent_key: &AgentPubKey,
+        cap_secret: holochain_zome_types::capability::CapSecret,
+    ) -> ConductorApiResult<()> {
+        use holochain_zome_types::capability::ZomeCallCapGrant;
+        use std::collections::BTreeSet;
+
         self.grant_zome_call_capability(GrantZomeCallCapabilityPayload {
             cell_id: request.cell_id,
             cap_grant: ZomeCallCapGrant {
                 tag: "zome-call-signing-key".to_string(),
                 access: holochain_zome_types::capability::CapAccess::Assigned {
-                    secret: cap_secret.into(),
+                    secret: cap_secret,
                     assignees: BTreeSet::from([signing_agent_key.clone()]),
                 },
                 functions: request.functions.unwrap_or(GrantedFunctions::All),
             },
         })
-        .await?;
-
-        Ok(crate::signing::client_signing::SigningCredentials {
-            signing_agent_key,
-            keypair,
-            cap_secret: cap_secret.into(),
-        })
+        .await
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(skip_all, err, fields(request = crate::util::admin_request_kind(&msg)))
+    )]
     async fn send(&self, msg: AdminRequest) -> ConductorApiResult<AdminResponse> {
+        #[cfg(feature = "metrics")]
+        let mut timer =
+            crate::metrics::RequestTimer::start(|| crate::metrics::RequestLabels::admin(&msg));
+
         let response: AdminResponse = self
             .tx
             .request(msg)
@@ -549,7 +1095,12 @@ impl AdminWebsocket {
             .map_err(ConductorApiError::WebsocketError)?;
         match response {
             AdminResponse::Error(error) => Err(ConductorApiError::ExternalApiWireError(error)),
-            _ => Ok(response),
+            _ => {
+                #[cfg(feature = "metrics")]
+                timer.succeeded();
+
+                Ok(response)
+            }
         }
     }
 }
@@ -1,8 +1,11 @@
 use crate::app_websocket_inner::AppWebsocketInner;
 use crate::signing::DynAgentSigner;
-use crate::{signing::sign_zome_call, ConductorApiError, ConductorApiResult};
+use crate::{
+    signing::{sign_error, sign_zome_call},
+    CapabilityWallet, ConductorApiError, ConductorApiResult,
+};
 use anyhow::{anyhow, Result};
-use holo_hash::AgentPubKey;
+use holo_hash::{AgentPubKey, DnaHash};
 use holochain_conductor_api::{
     AppAuthenticationToken, AppInfo, AppRequest, AppResponse, CellInfo, ProvisionedCell,
     ZomeCallParamsSigned,
@@ -17,6 +20,7 @@ use holochain_zome_types::{
     clone::ClonedCell,
     prelude::{CellId, ExternIO, FunctionName, RoleName, Timestamp, ZomeCallParams, ZomeName},
 };
+use parking_lot::RwLock;
 use std::fmt::Formatter;
 use std::net::ToSocketAddrs;
 use std::sync::Arc;
@@ -26,7 +30,7 @@ use std::sync::Arc;
 pub struct AppWebsocket {
     pub my_pub_key: AgentPubKey,
     inner: AppWebsocketInner,
-    app_info: AppInfo,
+    app_info: Arc<RwLock<AppInfo>>,
     signer: DynAgentSigner,
 }
 
@@ -35,7 +39,7 @@ impl std::fmt::Debug for AppWebsocket {
         f.debug_struct("AppWebsocket")
             .field("my_pub_key", &self.my_pub_key)
             .field("inner", &self.inner)
-            .field("app_info", &self.app_info)
+            .field("app_info", &*self.app_info.read())
             .finish()
     }
 }
@@ -190,11 +194,12 @@ impl AppWebsocket {
             .app_info()
             .await?
             .ok_or(ConductorApiError::AppNotFound)?;
+        signer.update_app_info(&app_info);
 
         Ok(AppWebsocket {
             my_pub_key: app_info.agent_pub_key.clone(),
             inner,
-            app_info,
+            app_info: Arc::new(RwLock::new(app_info)),
             signer,
         })
     }
@@ -208,7 +213,7 @@ impl AppWebsocket {
                     zome_name: _,
                     signal: _,
                 } => {
-                    if app_info.cell_info.values().any(|cells| {
+                    if app_info.read().cell_info.values().any(|cells| {
                         cells.iter().any(|cell_info| match cell_info {
                             CellInfo::Provisioned(cell) => cell.cell_id.eq(&cell_id),
                             CellInfo::Cloned(cell) => cell.cell_id.eq(&cell_id),
@@ -227,16 +232,26 @@ impl AppWebsocket {
         self.inner.app_info().await
     }
 
-    /// Get the cached app info held by this websocket.
+    /// Get a copy of the cached app info held by this websocket.
     ///
     /// In order to speed up internal operations, the app info is cached by the websocket after
     /// connection and refreshed as required. You cannot control the cache lifetime, but you can
     /// use the value and fallback to [AppWebsocket::app_info] if you need to ensure you have the
     /// latest info.
-    pub fn cached_app_info(&self) -> &AppInfo {
-        &self.app_info
+    ///
+    /// The cache is shared between all clones of this websocket.
+    pub fn cached_app_info(&self) -> AppInfo {
+        self.app_info.read().clone()
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(zome = %zome_name, fn_name = %fn_name, cell_id = tracing::field::Empty)
+        )
+    )]
     pub async fn call_zome(
         &self,
         target: ZomeCallTarget,
@@ -244,43 +259,144 @@ impl AppWebsocket {
         fn_name: FunctionName,
         payload: ExternIO,
     ) -> ConductorApiResult<ExternIO> {
-        let cell_id = match target {
-            ZomeCallTarget::CellId(cell_id) => cell_id,
-            ZomeCallTarget::RoleName(role_name) => self.get_cell_id_from_role_name(&role_name)?,
-            ZomeCallTarget::CloneId(clone_id) => self.get_cell_id_from_role_name(&clone_id.0)?,
+        let cell_id = match self.get_cell_id_from_target(&target) {
+            // The cell may have been created since the app info was cached
+            Err(ConductorApiError::CellNotFound) => {
+                self.refresh_cached_app_info().await?;
+                self.get_cell_id_from_target(&target)?
+            }
+            result => result?,
         };
 
+        #[cfg(feature = "tracing")]
+        tracing::Span::current().record("cell_id", tracing::field::debug(&cell_id));
+
+        self.signer
+            .prepare_for_fn(&cell_id, &zome_name, &fn_name)
+            .await
+            .map_err(|e| ConductorApiError::SignZomeCallError(e.to_string()))?;
+
         let (nonce, expires_at) =
             fresh_nonce(Timestamp::now()).map_err(ConductorApiError::FreshNonceError)?;
 
+        // Both are read at once, in case the credentials are being rotated
+        let (provenance, cap_secret) = self
+            .signer
+            .get_credentials_for_fn(&cell_id, &zome_name, &fn_name)
+            .ok_or(ConductorApiError::SignZomeCallError(
+                "Provenance not found".to_string(),
+            ))?;
         let params = ZomeCallParams {
-            provenance: self.signer.get_provenance(&cell_id).ok_or(
-                ConductorApiError::SignZomeCallError("Provenance not found".to_string()),
-            )?,
-            cap_secret: self.signer.get_cap_secret(&cell_id),
+            provenance,
+            cap_secret,
             cell_id: cell_id.clone(),
-            zome_name,
-            fn_name,
+            zome_name: zome_name.clone(),
+            fn_name: fn_name.clone(),
             payload,
             expires_at,
             nonce,
         };
-        let signed_zome_call = sign_zome_call(params, self.signer.clone())
+        let signed_zome_call = sign_zome_call(&cell_id, params, self.signer.clone())
+            .await
+            .map_err(sign_error)?;
+
+        self.send_signed_zome_call(signed_zome_call, Some((&zome_name, &fn_name)))
+            .await
+    }
+
+    /// Call a zome function in a cell of another agent, using a capability that they granted.
+    ///
+    /// The call is signed with the credentials that the signer of this websocket holds for the
+    /// app's own cell of the same DNA, and its provenance is the provenance that the signer gives
+    /// for that cell. For a [ClientAgentSigner](crate::ClientAgentSigner) this is the signing key
+    /// of the credentials, and for a [LairAgentSigner](crate::LairAgentSigner) set up with
+    /// [LairAgentSigner::add_credentials_for_app](crate::LairAgentSigner::add_credentials_for_app)
+    /// it is the agent key. The cell being called must be running on the same conductor.
+    ///
+    /// The cap secret is taken from a claim in the `wallet` on a grant made by the agent of
+    /// `cell_id`, which must cover the function. Claims on assigned grants are only used if the
+    /// provenance is one of their assignees, so a grant to be used with a `ClientAgentSigner` must
+    /// be assigned to its signing key rather than to the agent key.
+    ///
+    /// Fails with [ConductorApiError::CapClaimNotFound] if the wallet has no claim for the
+    /// function that can be used with the provenance.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            skip_all,
+            err,
+            fields(zome = %zome_name, fn_name = %fn_name, cell_id = ?cell_id)
+        )
+    )]
+    pub async fn call_zome_with_claim(
+        &self,
+        wallet: &CapabilityWallet,
+        cell_id: CellId,
+        zome_name: ZomeName,
+        fn_name: FunctionName,
+        payload: ExternIO,
+    ) -> ConductorApiResult<ExternIO> {
+        let own_cell_id = CellId::new(cell_id.dna_hash().clone(), self.my_pub_key.clone());
+        self.signer
+            .prepare(&own_cell_id)
             .await
             .map_err(|e| ConductorApiError::SignZomeCallError(e.to_string()))?;
+        let provenance = self.signer.get_provenance(&own_cell_id).ok_or(
+            ConductorApiError::SignZomeCallError("Provenance not found".to_string()),
+        )?;
+
+        let grantor = cell_id.agent_pubkey().clone();
+        let cap_secret = wallet
+            .get_cap_secret(&grantor, &provenance, &zome_name, &fn_name)
+            .ok_or_else(|| ConductorApiError::CapClaimNotFound(grantor.clone()))?;
 
-        self.signed_call_zome(signed_zome_call).await
+        let (nonce, expires_at) =
+            fresh_nonce(Timestamp::now()).map_err(ConductorApiError::FreshNonceError)?;
+
+        let params = ZomeCallParams {
+            provenance,
+            cap_secret: Some(cap_secret),
+            cell_id,
+            zome_name: zome_name.clone(),
+            fn_name: fn_name.clone(),
+            payload,
+            expires_at,
+            nonce,
+        };
+        let signed_zome_call = sign_zome_call(&own_cell_id, params, self.signer.clone())
+            .await
+            .map_err(sign_error)?;
+
+        self.send_signed_zome_call(signed_zome_call, Some((&zome_name, &fn_name)))
+            .await
     }
 
     pub async fn signed_call_zome(
         &self,
         signed_params: ZomeCallParamsSigned,
+    ) -> ConductorApiResult<ExternIO> {
+        self.send_signed_zome_call(signed_params, None).await
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "signed_call_zome", skip_all, err)
+    )]
+    async fn send_signed_zome_call(
+        &self,
+        signed_params: ZomeCallParamsSigned,
+        function: Option<(&ZomeName, &FunctionName)>,
     ) -> ConductorApiResult<ExternIO> {
         let app_request = AppRequest::CallZome(Box::new(signed_params));
-        let response = self.inner.send(app_request).await?;
+        let response = self.inner.send_for_fn(app_request, function).await?;
 
         match response {
-            AppResponse::ZomeCalled(result) => Ok(*result),
+            AppResponse::ZomeCalled(result) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(response_bytes = result.0.len(), "Zome call succeeded");
+
+                Ok(*result)
+            }
             _ => unreachable!("Unexpected response {:?}", response),
         }
     }
@@ -310,7 +426,10 @@ impl AppWebsocket {
         let app_request = AppRequest::CreateCloneCell(Box::new(msg));
         let response = self.inner.send(app_request).await?;
         match response {
-            AppResponse::CloneCellCreated(clone_cell) => Ok(clone_cell),
+            AppResponse::CloneCellCreated(clone_cell) => {
+                self.refresh_cached_app_info_after_change().await;
+                Ok(clone_cell)
+            }
             _ => unreachable!("Unexpected response {:?}", response),
         }
     }
@@ -322,7 +441,10 @@ impl AppWebsocket {
         let app_request = AppRequest::DisableCloneCell(Box::new(payload));
         let response = self.inner.send(app_request).await?;
         match response {
-            AppResponse::CloneCellDisabled => Ok(()),
+            AppResponse::CloneCellDisabled => {
+                self.refresh_cached_app_info_after_change().await;
+                Ok(())
+            }
             _ => unreachable!("Unexpected response {:?}", response),
         }
     }
@@ -334,7 +456,10 @@ impl AppWebsocket {
         let msg = AppRequest::EnableCloneCell(Box::new(payload));
         let response = self.inner.send(msg).await?;
         match response {
-            AppResponse::CloneCellEnabled(enabled_cell) => Ok(enabled_cell),
+            AppResponse::CloneCellEnabled(enabled_cell) => {
+                self.refresh_cached_app_info_after_change().await;
+                Ok(enabled_cell)
+            }
             _ => unreachable!("Unexpected response {:?}", response),
         }
     }
@@ -350,52 +475,48 @@ impl AppWebsocket {
 
     /// Gets a new copy of the [AppInfo] for the app this agent is connected to.
     ///
-    /// This is useful if you have made changes to the app, such as creating new clone cells, and need to refresh the app info.
-    pub async fn refresh_app_info(&mut self) -> Result<()> {
-        self.app_info = self
-            .app_info()
+    /// The cached app info is refreshed automatically after clone cell operations made through
+    /// this websocket, and when a zome call target cannot be found in the cache. This is useful if
+    /// the app has been changed by another client, for example by an admin creating clone cells.
+    ///
+    /// The refreshed app info is visible to all clones of this websocket, and is given to the signer
+    /// with [AgentSigner::update_app_info](crate::AgentSigner::update_app_info).
+    pub async fn refresh_app_info(&self) -> Result<()> {
+        self.refresh_cached_app_info()
             .await
-            .map_err(|err| anyhow!("Error fetching app_info {err:?}"))?
-            .ok_or(anyhow!("App doesn't exist"))?;
+            .map_err(|err| anyhow!("Error fetching app_info {err:?}"))
+    }
+
+    async fn refresh_cached_app_info(&self) -> ConductorApiResult<()> {
+        let app_info = self
+            .app_info()
+            .await?
+            .ok_or(ConductorApiError::AppNotFound)?;
+        self.signer.update_app_info(&app_info);
+        *self.app_info.write() = app_info;
 
         Ok(())
     }
 
-    fn get_cell_id_from_role_name(&self, role_name: &RoleName) -> ConductorApiResult<CellId> {
-        if is_clone_id(role_name) {
-            let base_role_name = get_base_role_name_from_clone_id(role_name);
-
-            let Some(role_cells) = self.app_info.cell_info.get(&base_role_name) else {
-                return Err(ConductorApiError::CellNotFound);
-            };
-
-            let maybe_clone_cell: Option<ClonedCell> =
-                role_cells.iter().find_map(|cell| match cell {
-                    CellInfo::Cloned(cloned_cell) => {
-                        if cloned_cell.clone_id.0.eq(role_name) {
-                            Some(cloned_cell.clone())
-                        } else {
-                            None
-                        }
-                    }
-                    _ => None,
-                });
-
-            let clone_cell = maybe_clone_cell.ok_or(ConductorApiError::CellNotFound)?;
-            Ok(clone_cell.cell_id)
-        } else {
-            let Some(role_cells) = self.app_info.cell_info.get(role_name) else {
-                return Err(ConductorApiError::CellNotFound);
-            };
-
-            let maybe_provisioned: Option<ProvisionedCell> =
-                role_cells.iter().find_map(|cell| match cell {
-                    CellInfo::Provisioned(provisioned_cell) => Some(provisioned_cell.clone()),
-                    _ => None,
-                });
-
-            let provisioned_cell = maybe_provisioned.ok_or(ConductorApiError::CellNotFound)?;
-            Ok(provisioned_cell.cell_id)
+    /// Refresh the cached app info after a change that the conductor has already made.
+    ///
+    /// Failing to refresh doesn't undo the change, so the error is not returned. The cache will be
+    /// refreshed again when a zome call target cannot be found in it.
+    async fn refresh_cached_app_info_after_change(&self) {
+        if let Err(_e) = self.refresh_cached_app_info().await {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(error = %_e, "Failed to refresh app info after changing clone cells");
+        }
+    }
+
+    fn get_cell_id_from_target(&self, target: &ZomeCallTarget) -> ConductorApiResult<CellId> {
+        let app_info = self.app_info.read();
+        match target {
+            ZomeCallTarget::CellId(cell_id) => Ok(cell_id.clone()),
+            ZomeCallTarget::RoleName(role_name) => get_cell_id_from_role_name(&app_info, role_name),
+            ZomeCallTarget::CloneId(clone_id) => get_cell_id_from_role_name(&app_info, &clone_id.0),
+            ZomeCallTarget::DnaHash(dna_hash) => get_cell_id_from_dna_hash(&app_info, dna_hash),
+            ZomeCallTarget::CloneName(name) => get_cell_id_from_clone_name(&app_info, name),
         }
     }
 
@@ -430,18 +551,29 @@ impl AppWebsocket {
     }
 }
 
+/// The cell to make a zome call to.
+///
+/// Targets other than [ZomeCallTarget::CellId] are resolved against the cached [AppInfo]. If no
+/// cell is found, the cache is refreshed once before failing, so that clone cells created after
+/// connecting can be called.
 pub enum ZomeCallTarget {
     CellId(CellId),
     /// Call a cell by its role name.
-    ///
-    /// Note that when using clone cells, if you create them after creating the [AppWebsocket], you will need to call [AppWebsocket::refresh_app_info]
-    /// for the right CellId to be found to make the call.
     RoleName(RoleName),
     /// Call a cell by its clone id.
-    ///
-    /// Note that when using clone cells, if you create them after creating the [AppWebsocket], you will need to call [AppWebsocket::refresh_app_info]
-    /// for the right CellId to be found to make the call.
     CloneId(CloneId),
+    /// Call a cell by its DNA hash.
+    ///
+    /// Both provisioned and enabled clone cells are searched, so disabled clone cells are never
+    /// called. If more than one of these cells has the given DNA hash, the call fails with
+    /// [ConductorApiError::AmbiguousCellTarget].
+    DnaHash(DnaHash),
+    /// Call a clone cell by the name it was given when it was created.
+    ///
+    /// Only enabled clone cells are searched. Clone names are not required to be unique, so if more
+    /// than one enabled clone cell has the given name, the call fails with
+    /// [ConductorApiError::AmbiguousCellTarget].
+    CloneName(String),
 }
 
 impl From<CellId> for ZomeCallTarget {
@@ -462,6 +594,93 @@ impl From<CloneId> for ZomeCallTarget {
     }
 }
 
+impl From<DnaHash> for ZomeCallTarget {
+    fn from(dna_hash: DnaHash) -> Self {
+        ZomeCallTarget::DnaHash(dna_hash)
+    }
+}
+
+fn get_cell_id_from_role_name(
+    app_info: &AppInfo,
+    role_name: &RoleName,
+) -> ConductorApiResult<CellId> {
+    if is_clone_id(role_name) {
+        let base_role_name = get_base_role_name_from_clone_id(role_name);
+
+        let Some(role_cells) = app_info.cell_info.get(&base_role_name) else {
+            return Err(ConductorApiError::CellNotFound);
+        };
+
+        let maybe_clone_cell: Option<ClonedCell> = role_cells.iter().find_map(|cell| match cell {
+            CellInfo::Cloned(cloned_cell) => {
+                if cloned_cell.clone_id.0.eq(role_name) {
+                    Some(cloned_cell.clone())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        });
+
+        let clone_cell = maybe_clone_cell.ok_or(ConductorApiError::CellNotFound)?;
+        Ok(clone_cell.cell_id)
+    } else {
+        let Some(role_cells) = app_info.cell_info.get(role_name) else {
+            return Err(ConductorApiError::CellNotFound);
+        };
+
+        let maybe_provisioned: Option<ProvisionedCell> =
+            role_cells.iter().find_map(|cell| match cell {
+                CellInfo::Provisioned(provisioned_cell) => Some(provisioned_cell.clone()),
+                _ => None,
+            });
+
+        let provisioned_cell = maybe_provisioned.ok_or(ConductorApiError::CellNotFound)?;
+        Ok(provisioned_cell.cell_id)
+    }
+}
+
+fn get_cell_id_from_dna_hash(app_info: &AppInfo, dna_hash: &DnaHash) -> ConductorApiResult<CellId> {
+    let matching = app_info
+        .cell_info
+        .values()
+        .flatten()
+        .filter_map(|cell| match cell {
+            CellInfo::Provisioned(provisioned_cell) => Some(&provisioned_cell.cell_id),
+            CellInfo::Cloned(cloned_cell) if cloned_cell.enabled => Some(&cloned_cell.cell_id),
+            _ => None,
+        })
+        .filter(|cell_id| cell_id.dna_hash() == dna_hash)
+        .cloned()
+        .collect();
+
+    single_cell_id(matching)
+}
+
+fn get_cell_id_from_clone_name(app_info: &AppInfo, name: &str) -> ConductorApiResult<CellId> {
+    let matching = app_info
+        .cell_info
+        .values()
+        .flatten()
+        .filter_map(|cell| match cell {
+            CellInfo::Cloned(cloned_cell) if cloned_cell.enabled && cloned_cell.name == name => {
+                Some(cloned_cell.cell_id.clone())
+            }
+            _ => None,
+        })
+        .collect();
+
+    single_cell_id(matching)
+}
+
+fn single_cell_id(mut matching: Vec<CellId>) -> ConductorApiResult<CellId> {
+    match matching.len() {
+        0 => Err(ConductorApiError::CellNotFound),
+        1 => Ok(matching.remove(0)),
+        _ => Err(ConductorApiError::AmbiguousCellTarget(matching)),
+    }
+}
+
 fn is_clone_id(role_name: &RoleName) -> bool {
     role_name.as_str().contains('.')
 }
@@ -6,6 +6,7 @@ use holochain_conductor_api::{
 };
 use holochain_types::signal::Signal;
 use holochain_websocket::{connect, ConnectRequest, WebsocketConfig, WebsocketSender};
+use holochain_zome_types::prelude::{FunctionName, ZomeName};
 use std::fmt::Formatter;
 use std::{net::ToSocketAddrs, sync::Arc};
 use tokio::sync::Mutex;
@@ -33,17 +34,24 @@ impl AppWebsocketInner {
     }
 
     /// Connect to a Conductor API app websocket with a custom [WebsocketConfig].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
     pub async fn connect_with_config(
         socket_addr: impl ToSocketAddrs,
         websocket_config: Arc<WebsocketConfig>,
     ) -> ConductorApiResult<Self> {
         let mut last_err = None;
         for addr in socket_addr.to_socket_addrs()? {
+            #[cfg(feature = "tracing")]
+            tracing::debug!(%addr, "Connecting to app websocket");
+
             let request: ConnectRequest = addr.into();
 
             match Self::connect_with_config_and_request(websocket_config.clone(), request).await {
                 Ok(app_ws) => return Ok(app_ws),
                 Err(e) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(%addr, error = %e, "Failed to connect to app websocket");
+
                     last_err = Some(e);
                 }
             }
@@ -57,6 +65,7 @@ impl AppWebsocketInner {
     }
 
     /// Connect to a Conductor API app websocket with a custom [WebsocketConfig] and [ConnectRequest].
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
     pub async fn connect_with_config_and_request(
         websocket_config: Arc<WebsocketConfig>,
         request: ConnectRequest,
@@ -71,6 +80,11 @@ impl AppWebsocketInner {
             async move {
                 while let Ok(msg) = rx.recv::<AppResponse>().await {
                     if let holochain_websocket::ReceiveMessage::Signal(signal_bytes) = msg {
+                        #[cfg(feature = "tracing")]
+                        tracing::trace!(signal_bytes = signal_bytes.len(), "Received signal");
+                        #[cfg(feature = "metrics")]
+                        crate::metrics::record_signal(signal_bytes.len());
+
                         let mut event_emitter = mutex.lock().await;
                         event_emitter.emit("signal", signal_bytes);
                     }
@@ -105,6 +119,7 @@ impl AppWebsocketInner {
         }
     }
 
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
     pub(crate) async fn authenticate(
         &self,
         token: AppAuthenticationToken,
@@ -116,6 +131,30 @@ impl AppWebsocketInner {
     }
 
     pub(crate) async fn send(&self, msg: AppRequest) -> ConductorApiResult<AppResponse> {
+        self.send_for_fn(msg, None).await
+    }
+
+    /// Send a request for a call to the given zome function, if known, so that the call doesn't
+    /// need to be decoded again to record it.
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "send",
+            skip_all,
+            err,
+            fields(request = crate::util::app_request_kind(&msg))
+        )
+    )]
+    pub(crate) async fn send_for_fn(
+        &self,
+        msg: AppRequest,
+        _function: Option<(&ZomeName, &FunctionName)>,
+    ) -> ConductorApiResult<AppResponse> {
+        #[cfg(feature = "metrics")]
+        let mut timer = crate::metrics::RequestTimer::start(|| {
+            crate::metrics::RequestLabels::app(&msg, _function)
+        });
+
         let response = self
             .tx
             .request(msg)
@@ -124,7 +163,12 @@ impl AppWebsocketInner {
 
         match response {
             AppResponse::Error(error) => Err(ConductorApiError::ExternalApiWireError(error)),
-            _ => Ok(response),
+            _ => {
+                #[cfg(feature = "metrics")]
+                timer.succeeded();
+
+                Ok(response)
+            }
         }
     }
 }
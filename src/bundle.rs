@@ -0,0 +1,267 @@
+use crate::{ConductorApiError, ConductorApiResult};
+use holo_hash::{AgentPubKey, DnaHash};
+use holochain_types::{
+    prelude::{
+        AppBundle, AppManifest, AppRoleManifest, CellProvisioning, DnaBundle, DnaModifiersOpt,
+        NetworkSeed, RoleSettings, RoleSettingsMap, YamlProperties,
+    },
+    web_app::WebAppBundle,
+};
+use holochain_zome_types::prelude::{CellId, RoleName, ZomeName};
+use mr_bundle::Location;
+use std::path::Path;
+
+#[cfg(feature = "bundle_packing")]
+pub(crate) mod packing;
+
+#[cfg(feature = "web_app")]
+pub(crate) mod web_app;
+
+/// What a DNA bundle contains, and the hash it has with the modifiers it was inspected with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DnaInfo {
+    pub name: String,
+    /// The hash of the DNA with the modifiers applied, which is the DNA hash of the cells that
+    /// will be created from it.
+    pub dna_hash: DnaHash,
+    /// The modifiers that were applied over the modifiers in the DNA manifest.
+    pub modifiers: DnaModifiersOpt<YamlProperties>,
+    pub integrity_zomes: Vec<ZomeName>,
+    pub coordinator_zomes: Vec<ZomeName>,
+}
+
+/// A role of an app bundle, and the DNA that will be installed for it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoleInfo {
+    pub name: RoleName,
+    pub provisioning: Option<CellProvisioning>,
+    pub clone_limit: u32,
+    /// The DNA for the role, or `None` if the bundle does not include a DNA for it.
+    ///
+    /// DNAs that the manifest refers to by a local path or URL are not read when inspecting a
+    /// bundle, so that inspecting an untrusted bundle never reads other files or makes network
+    /// requests, and `None` is returned for them too.
+    pub dna: Option<DnaInfo>,
+}
+
+impl RoleInfo {
+    /// The id of the cell that will be created for this role when the app is installed for the
+    /// given agent.
+    pub fn cell_id(&self, agent_key: &AgentPubKey) -> Option<CellId> {
+        let dna = self.dna.as_ref()?;
+        Some(CellId::new(dna.dna_hash.clone(), agent_key.clone()))
+    }
+}
+
+/// What an app bundle contains.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppBundleInfo {
+    pub manifest: AppManifest,
+    pub roles: Vec<RoleInfo>,
+}
+
+/// What a web app bundle contains.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebAppBundleInfo {
+    pub app: AppBundleInfo,
+    /// The size of the UI zip file in bytes.
+    pub ui_size: usize,
+}
+
+/// What a bundle file contains, depending on its type.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleInfo {
+    App(AppBundleInfo),
+    Dna(DnaInfo),
+    WebApp(WebAppBundleInfo),
+}
+
+/// The settings that an app would be installed with, which change the hashes of its DNAs.
+///
+/// These are the same settings that are given to
+/// [AdminWebsocket::install_app](crate::AdminWebsocket::install_app) in an
+/// [InstallAppPayload](crate::InstallAppPayload), so that the DNA hashes, and so the cell ids, of
+/// an app can be predicted before it is installed.
+#[derive(Debug, Default)]
+pub struct InspectOptions {
+    /// Overrides the network seed of every role.
+    pub network_seed: Option<NetworkSeed>,
+    /// Overrides the modifiers of individual roles.
+    pub roles_settings: Option<RoleSettingsMap>,
+}
+
+/// Inspect a `.happ`, `.dna` or `.webhapp` file, depending on its extension.
+///
+/// A `.dna` file has no roles, so only the network seed of the options is applied to it.
+pub async fn inspect_bundle_file(
+    path: impl AsRef<Path>,
+    options: &InspectOptions,
+) -> ConductorApiResult<BundleInfo> {
+    let path = path.as_ref();
+    let bytes = tokio::fs::read(path).await?;
+
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("happ") => Ok(BundleInfo::App(inspect_app_bundle(&bytes, options).await?)),
+        Some("dna") => {
+            let mut modifiers = DnaModifiersOpt::default();
+            if let Some(network_seed) = &options.network_seed {
+                modifiers = modifiers.with_network_seed(network_seed.clone());
+            }
+            Ok(BundleInfo::Dna(
+                inspect_dna_bundle(&bytes, modifiers).await?,
+            ))
+        }
+        Some("webhapp") => Ok(BundleInfo::WebApp(
+            inspect_web_app_bundle(&bytes, options).await?,
+        )),
+        _ => Err(bundle_error(format!(
+            "Not a .happ, .dna or .webhapp file: {}",
+            path.display()
+        ))),
+    }
+}
+
+/// Inspect an encoded app bundle, computing the DNA hash of each role as it would be installed
+/// with the given options.
+pub async fn inspect_app_bundle(
+    bytes: &[u8],
+    options: &InspectOptions,
+) -> ConductorApiResult<AppBundleInfo> {
+    let bundle = AppBundle::decode(bytes)
+        .map_err(|e| bundle_error(format!("Failed to decode app bundle: {e}")))?;
+    inspect_decoded_app_bundle(bundle, options).await
+}
+
+/// Inspect an encoded web app bundle, and the app bundle inside it.
+pub async fn inspect_web_app_bundle(
+    bytes: &[u8],
+    options: &InspectOptions,
+) -> ConductorApiResult<WebAppBundleInfo> {
+    let bundle = WebAppBundle::decode(bytes)
+        .map_err(|e| bundle_error(format!("Failed to decode web app bundle: {e}")))?;
+    let ui_size = bundle
+        .web_ui_zip_bytes()
+        .await
+        .map_err(|e| bundle_error(format!("Failed to read UI: {e}")))?
+        .len();
+    let app_bundle = bundle
+        .happ_bundle()
+        .await
+        .map_err(|e| bundle_error(format!("Failed to read app bundle: {e}")))?;
+
+    Ok(WebAppBundleInfo {
+        app: inspect_decoded_app_bundle(app_bundle, options).await?,
+        ui_size,
+    })
+}
+
+/// Inspect an encoded DNA bundle, computing its hash with the given modifiers applied over the
+/// modifiers in its manifest.
+pub async fn inspect_dna_bundle(
+    bytes: &[u8],
+    modifiers: DnaModifiersOpt<YamlProperties>,
+) -> ConductorApiResult<DnaInfo> {
+    let bundle = DnaBundle::decode(bytes)
+        .map_err(|e| bundle_error(format!("Failed to decode DNA bundle: {e}")))?;
+    inspect_decoded_dna_bundle(bundle, modifiers).await
+}
+
+async fn inspect_decoded_app_bundle(
+    bundle: AppBundle,
+    options: &InspectOptions,
+) -> ConductorApiResult<AppBundleInfo> {
+    let manifest = bundle.manifest().clone();
+
+    let mut roles = Vec::new();
+    for role in manifest.app_roles() {
+        let dna = match &role.dna.location {
+            Some(location @ Location::Bundled(_)) => {
+                let bytes = bundle.resolve(location).await.map_err(|e| {
+                    bundle_error(format!("Failed to read DNA for role {}: {e}", role.name))
+                })?;
+                let dna_bundle = DnaBundle::decode(&bytes).map_err(|e| {
+                    bundle_error(format!("Failed to decode DNA for role {}: {e}", role.name))
+                })?;
+                let mut dna =
+                    inspect_decoded_dna_bundle(dna_bundle, role_modifiers(&role, options)).await?;
+                if let Some(RoleSettings::UseExisting { cell_id }) = role_settings(&role, options) {
+                    dna.dna_hash = cell_id.dna_hash().clone();
+                }
+                Some(dna)
+            }
+            Some(Location::Path(_) | Location::Url(_)) | None => None,
+        };
+
+        roles.push(RoleInfo {
+            name: role.name,
+            provisioning: role.provisioning,
+            clone_limit: role.dna.clone_limit,
+            dna,
+        });
+    }
+
+    Ok(AppBundleInfo { manifest, roles })
+}
+
+async fn inspect_decoded_dna_bundle(
+    bundle: DnaBundle,
+    modifiers: DnaModifiersOpt<YamlProperties>,
+) -> ConductorApiResult<DnaInfo> {
+    let serialized_modifiers = modifiers.clone().serialized()?;
+    let (dna_file, dna_hash) = bundle
+        .into_dna_file(serialized_modifiers)
+        .await
+        .map_err(|e| bundle_error(format!("Failed to build DNA: {e}")))?;
+    let dna_def = dna_file.dna_def();
+
+    Ok(DnaInfo {
+        name: dna_def.name.clone(),
+        dna_hash,
+        modifiers,
+        integrity_zomes: dna_def
+            .integrity_zomes
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect(),
+        coordinator_zomes: dna_def
+            .coordinator_zomes
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect(),
+    })
+}
+
+fn role_settings<'a>(
+    role: &AppRoleManifest,
+    options: &'a InspectOptions,
+) -> Option<&'a RoleSettings> {
+    options.roles_settings.as_ref()?.get(&role.name)
+}
+
+/// The modifiers of a role, overridden by the options in the same order as when installing.
+fn role_modifiers(
+    role: &AppRoleManifest,
+    options: &InspectOptions,
+) -> DnaModifiersOpt<YamlProperties> {
+    let mut modifiers = role.dna.modifiers.clone();
+    if let Some(network_seed) = &options.network_seed {
+        modifiers = modifiers.with_network_seed(network_seed.clone());
+    }
+    if let Some(RoleSettings::Provisioned {
+        modifiers: Some(role_modifiers),
+        ..
+    }) = role_settings(role, options)
+    {
+        if let Some(network_seed) = &role_modifiers.network_seed {
+            modifiers = modifiers.with_network_seed(network_seed.clone());
+        }
+        if let Some(properties) = &role_modifiers.properties {
+            modifiers = modifiers.with_properties(properties.clone());
+        }
+    }
+    modifiers
+}
+
+fn bundle_error(message: impl Into<String>) -> ConductorApiError {
+    ConductorApiError::BundleError(message.into())
+}
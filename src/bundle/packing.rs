@@ -0,0 +1,93 @@
+use crate::{ConductorApiError, ConductorApiResult};
+use holochain_types::prelude::{AppManifest, DnaBundle, DnaManifest, ValidatedDnaManifest};
+use mr_bundle::{Bundle, Location, Manifest, ResourceBytes};
+use std::path::{Path, PathBuf};
+
+/// Pack a DNA bundle from a `dna.yaml` manifest, as `hc dna pack` does.
+///
+/// The wasm files that the manifest refers to with `bundled` are read relative to the directory of
+/// the manifest. Returns the encoded bundle, which can be written to a `.dna` file.
+pub fn pack_dna_bundle(manifest_path: impl AsRef<Path>) -> ConductorApiResult<Vec<u8>> {
+    let manifest_path = manifest_path.as_ref();
+    let manifest: DnaManifest = read_manifest(manifest_path)?;
+    let manifest = ValidatedDnaManifest::try_from(manifest)
+        .map_err(|e| packing_error(manifest_path, format!("Invalid manifest: {e}")))?;
+
+    let root_dir = manifest_dir(manifest_path);
+    let resources = read_resources(&root_dir, manifest.locations(), |path| {
+        Ok(std::fs::read(path)?)
+    })?;
+
+    DnaBundle::new(manifest, resources, root_dir)
+        .map_err(|e| packing_error(manifest_path, e.to_string()))?
+        .encode()
+        .map_err(|e| packing_error(manifest_path, e.to_string()))
+}
+
+/// Pack an app bundle from a `happ.yaml` manifest, as `hc app pack` does.
+///
+/// The DNA files that the manifest refers to with `bundled` are read relative to the directory of
+/// the manifest. Every `bundled` location of an app manifest is the DNA of a role, so a location
+/// that is not a `.dna` bundle is taken to be a DNA manifest, such as a `dna.yaml`, and is packed
+/// with [pack_dna_bundle] first. An app can mix both, and no `.dna` files need to be built
+/// beforehand. Returns the encoded bundle, which can be installed with
+/// [AppBundleSource::Bytes](crate::AppBundleSource::Bytes).
+pub fn pack_app_bundle(manifest_path: impl AsRef<Path>) -> ConductorApiResult<Vec<u8>> {
+    let manifest_path = manifest_path.as_ref();
+    let manifest: AppManifest = read_manifest(manifest_path)?;
+
+    let root_dir = manifest_dir(manifest_path);
+    let resources = read_resources(&root_dir, manifest.locations(), |path| {
+        if path.extension().is_some_and(|extension| extension == "dna") {
+            Ok(std::fs::read(path)?)
+        } else {
+            pack_dna_bundle(path)
+        }
+    })?;
+
+    // The DNAs are already encoded, so the bundle is made directly rather than with
+    // `AppBundle::new`, which takes decoded DNA bundles
+    Bundle::new(manifest, resources, root_dir)
+        .map_err(|e| packing_error(manifest_path, e.to_string()))?
+        .encode()
+        .map_err(|e| packing_error(manifest_path, e.to_string()))
+}
+
+fn read_manifest<M: serde::de::DeserializeOwned>(manifest_path: &Path) -> ConductorApiResult<M> {
+    let yaml = std::fs::read(manifest_path)?;
+    serde_yaml::from_slice(&yaml)
+        .map_err(|e| packing_error(manifest_path, format!("Invalid manifest: {e}")))
+}
+
+fn manifest_dir(manifest_path: &Path) -> PathBuf {
+    manifest_path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default()
+}
+
+/// Read the resources for the `bundled` locations of a manifest, keyed by their location.
+fn read_resources(
+    root_dir: &Path,
+    locations: Vec<Location>,
+    read: impl Fn(&Path) -> ConductorApiResult<Vec<u8>>,
+) -> ConductorApiResult<Vec<(PathBuf, ResourceBytes)>> {
+    locations
+        .into_iter()
+        .filter_map(|location| match location {
+            Location::Bundled(path) => Some(path),
+            _ => None,
+        })
+        .map(|path| {
+            let bytes = read(&root_dir.join(&path))?;
+            Ok((path, bytes.into()))
+        })
+        .collect()
+}
+
+fn packing_error(manifest_path: &Path, message: impl std::fmt::Display) -> ConductorApiError {
+    ConductorApiError::BundleError(format!(
+        "Failed to pack {}: {message}",
+        manifest_path.display()
+    ))
+}
@@ -0,0 +1,104 @@
+use crate::{ConductorApiError, ConductorApiResult};
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+
+/// The largest total size that the files of a UI may have once extracted.
+pub(crate) const MAX_UI_BYTES: u64 = 512 * 1024 * 1024;
+
+/// Extract a web app UI zip into `dir`, which must not exist or be empty.
+///
+/// Entries whose paths would be written outside of `dir`, such as absolute paths or paths
+/// containing `..`, and symbolic links are rejected rather than skipped, because a UI with missing
+/// files would be broken anyway. Nothing is written unless every entry is valid.
+///
+/// Files are streamed to disk, and extraction stops with an error once more than [MAX_UI_BYTES]
+/// have been written, whatever sizes the zip claims its entries have. If extraction fails part way
+/// through, `dir` is removed again.
+pub(crate) fn extract_ui_zip(zip_bytes: &[u8], dir: &Path) -> ConductorApiResult<()> {
+    if dir.exists() && dir.read_dir()?.next().is_some() {
+        return Err(ui_error(format!(
+            "Directory is not empty: {}",
+            dir.display()
+        )));
+    }
+
+    let mut archive = zip::ZipArchive::new(Cursor::new(zip_bytes))
+        .map_err(|e| ui_error(format!("Invalid UI zip: {e}")))?;
+
+    let mut entries = Vec::with_capacity(archive.len());
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index(i)
+            .map_err(|e| ui_error(format!("Invalid UI zip: {e}")))?;
+        let Some(path) = entry.enclosed_name() else {
+            return Err(ui_error(format!(
+                "UI zip entry is outside of the UI directory: {}",
+                entry.name()
+            )));
+        };
+        if entry.is_symlink() {
+            return Err(ui_error(format!(
+                "UI zip entry is a symbolic link: {}",
+                entry.name()
+            )));
+        }
+        entries.push((i, path, entry.is_dir()));
+    }
+
+    std::fs::create_dir_all(dir)?;
+    let extracted = write_entries(&mut archive, entries, dir);
+    if extracted.is_err() {
+        // The directory was empty before, so nothing but the partly extracted UI is removed
+        let _ = std::fs::remove_dir_all(dir);
+    }
+    extracted
+}
+
+fn write_entries(
+    archive: &mut zip::ZipArchive<Cursor<&[u8]>>,
+    entries: Vec<(usize, PathBuf, bool)>,
+    dir: &Path,
+) -> ConductorApiResult<()> {
+    let mut remaining = MAX_UI_BYTES;
+    for (i, path, is_dir) in entries {
+        let path = dir.join(path);
+        if is_dir {
+            std::fs::create_dir_all(&path)?;
+            continue;
+        }
+
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let entry = archive
+            .by_index(i)
+            .map_err(|e| ui_error(format!("Invalid UI zip: {e}")))?;
+        let mut file = std::fs::File::create(path)?;
+        // Read one byte more than is allowed, to tell whether the limit was exceeded
+        let written = std::io::copy(&mut entry.take(remaining + 1), &mut file)?;
+        if written > remaining {
+            return Err(ui_error(format!(
+                "UI is larger than {MAX_UI_BYTES} bytes when extracted"
+            )));
+        }
+        remaining -= written;
+    }
+
+    Ok(())
+}
+
+/// Remove a UI directory that was extracted for an app which then failed to install.
+pub(crate) async fn remove_ui_dir(dir: PathBuf) {
+    let removed = match tokio::task::spawn_blocking(move || std::fs::remove_dir_all(dir)).await {
+        Ok(removed) => removed.map_err(|e| e.to_string()),
+        Err(e) => Err(e.to_string()),
+    };
+    if let Err(_e) = removed {
+        #[cfg(feature = "tracing")]
+        tracing::warn!(error = %_e, "Failed to remove UI directory after the app failed to install");
+    }
+}
+
+fn ui_error(message: impl Into<String>) -> ConductorApiError {
+    ConductorApiError::BundleError(message.into())
+}
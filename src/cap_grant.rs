@@ -0,0 +1,230 @@
+use crate::{AdminWebsocket, ConductorApiError, ConductorApiResult};
+use holo_hash::AgentPubKey;
+use holochain_zome_types::{
+    capability::{CapAccess, CapSecret, GrantedFunctions, ZomeCallCapGrant, CAP_SECRET_BYTES},
+    cell::CellId,
+    prelude::{FunctionName, GrantZomeCallCapabilityPayload, ZomeName},
+};
+use rand::{rngs::OsRng, RngCore};
+use std::collections::BTreeSet;
+
+#[derive(Debug, Clone)]
+enum Access {
+    Unrestricted,
+    Transferable,
+    Assigned(BTreeSet<AgentPubKey>),
+}
+
+/// Builds a capability grant for calling zome functions in a cell, and grants it with
+/// [AdminWebsocket::grant_zome_call_capability].
+///
+/// Created with [AdminWebsocket::build_cap_grant]. There is no default access, so one of
+/// [CapGrantBuilder::unrestricted], [CapGrantBuilder::transferable] or
+/// [CapGrantBuilder::assigned_to] must be called before the grant is sent. Every zome that a
+/// function is listed for is checked against the cell's DNA definition before the grant is sent.
+///
+/// The [Debug] output of the builder does not include the secret.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::net::Ipv4Addr;
+/// use holochain_client::{AdminWebsocket, AgentPubKey, CellId};
+///
+/// async fn grant_read_only(cell_id: CellId, agent_key: AgentPubKey) {
+///     let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, 30_000)).await.unwrap();
+///     let cap_grant = admin_ws
+///         .build_cap_grant(cell_id)
+///         .tag("read-only")
+///         .assigned_to(agent_key)
+///         .function("posts", "get_post")
+///         .function("posts", "list_posts")
+///         .grant()
+///         .await
+///         .unwrap();
+/// }
+/// ```
+#[derive(Clone)]
+pub struct CapGrantBuilder<'a> {
+    admin_ws: &'a AdminWebsocket,
+    cell_id: CellId,
+    tag: String,
+    access: Option<Access>,
+    secret: Option<CapSecret>,
+    functions: Option<BTreeSet<(ZomeName, FunctionName)>>,
+}
+
+impl std::fmt::Debug for CapGrantBuilder<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CapGrantBuilder")
+            .field("cell_id", &self.cell_id)
+            .field("tag", &self.tag)
+            .field("access", &self.access)
+            .field("secret", &self.secret.as_ref().map(|_| "<redacted>"))
+            .field("functions", &self.functions)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a> CapGrantBuilder<'a> {
+    pub(crate) fn new(admin_ws: &'a AdminWebsocket, cell_id: CellId) -> Self {
+        Self {
+            admin_ws,
+            cell_id,
+            tag: "zome-call".to_string(),
+            access: None,
+            secret: None,
+            functions: None,
+        }
+    }
+
+    /// Set the tag of the grant, which is used to identify it.
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = tag.into();
+        self
+    }
+
+    /// Allow anyone to call the granted functions, without a secret.
+    pub fn unrestricted(mut self) -> Self {
+        self.access = Some(Access::Unrestricted);
+        self
+    }
+
+    /// Allow anyone who knows the secret to call the granted functions.
+    pub fn transferable(mut self) -> Self {
+        self.access = Some(Access::Transferable);
+        self
+    }
+
+    /// Allow the given agent to call the granted functions with the secret.
+    ///
+    /// Can be called more than once to assign the grant to several agents.
+    pub fn assigned_to(mut self, agent: AgentPubKey) -> Self {
+        match &mut self.access {
+            Some(Access::Assigned(assignees)) => {
+                assignees.insert(agent);
+            }
+            _ => self.access = Some(Access::Assigned(BTreeSet::from([agent]))),
+        }
+        self
+    }
+
+    /// Allow the given agents to call the granted functions with the secret.
+    pub fn assignees(self, agents: impl IntoIterator<Item = AgentPubKey>) -> Self {
+        agents
+            .into_iter()
+            .fold(self, |builder, agent| builder.assigned_to(agent))
+    }
+
+    /// Use the given secret rather than generating a random one.
+    ///
+    /// Ignored for unrestricted grants.
+    pub fn secret(mut self, secret: CapSecret) -> Self {
+        self.secret = Some(secret);
+        self
+    }
+
+    /// Grant access to a function of a zome.
+    pub fn function(
+        mut self,
+        zome_name: impl Into<ZomeName>,
+        fn_name: impl Into<FunctionName>,
+    ) -> Self {
+        self.functions
+            .get_or_insert_with(BTreeSet::new)
+            .insert((zome_name.into(), fn_name.into()));
+        self
+    }
+
+    /// Grant access to each of the given functions, as pairs of zome name and function name.
+    ///
+    /// An empty list grants access to no functions, and is rejected by [CapGrantBuilder::grant].
+    pub fn functions<Z, F>(mut self, functions: impl IntoIterator<Item = (Z, F)>) -> Self
+    where
+        Z: Into<ZomeName>,
+        F: Into<FunctionName>,
+    {
+        self.functions.get_or_insert_with(BTreeSet::new);
+        functions
+            .into_iter()
+            .fold(self, |builder, (zome_name, fn_name)| {
+                builder.function(zome_name, fn_name)
+            })
+    }
+
+    /// Grant access to all functions of the cell.
+    pub fn all_functions(mut self) -> Self {
+        self.functions = None;
+        self
+    }
+
+    /// Validate the grant, send it to the conductor, and return the grant that was created.
+    ///
+    /// The returned grant includes its secret, which callers need to use it. Fails with
+    /// [ConductorApiError::InvalidCapGrant] if no access was chosen, if a listed zome does not
+    /// exist in the cell's DNA, or if an empty list of functions was given.
+    pub async fn grant(self) -> ConductorApiResult<ZomeCallCapGrant> {
+        let Some(access) = self.access else {
+            return Err(ConductorApiError::InvalidCapGrant(
+                "No access was chosen for the grant".to_string(),
+            ));
+        };
+        let functions = match self.functions {
+            None => GrantedFunctions::All,
+            Some(functions) if functions.is_empty() => {
+                return Err(ConductorApiError::InvalidCapGrant(
+                    "No functions to grant access to".to_string(),
+                ));
+            }
+            Some(functions) => {
+                let dna_def = self
+                    .admin_ws
+                    .get_dna_definition(self.cell_id.dna_hash().clone())
+                    .await?;
+                let zome_exists = |zome_name: &ZomeName| {
+                    dna_def
+                        .integrity_zomes
+                        .iter()
+                        .map(|(name, _)| name)
+                        .chain(dna_def.coordinator_zomes.iter().map(|(name, _)| name))
+                        .any(|name| name == zome_name)
+                };
+                if let Some((zome_name, _)) = functions
+                    .iter()
+                    .find(|(zome_name, _)| !zome_exists(zome_name))
+                {
+                    return Err(ConductorApiError::InvalidCapGrant(format!(
+                        "Zome {zome_name} does not exist in DNA {}",
+                        self.cell_id.dna_hash()
+                    )));
+                }
+                GrantedFunctions::Listed(functions)
+            }
+        };
+
+        let secret = self.secret.unwrap_or_else(|| {
+            let mut secret = [0; CAP_SECRET_BYTES];
+            OsRng.fill_bytes(&mut secret);
+            secret.into()
+        });
+        let access = match access {
+            Access::Unrestricted => CapAccess::Unrestricted,
+            Access::Transferable => CapAccess::Transferable { secret },
+            Access::Assigned(assignees) => CapAccess::Assigned { secret, assignees },
+        };
+
+        let cap_grant = ZomeCallCapGrant {
+            tag: self.tag,
+            access,
+            functions,
+        };
+        self.admin_ws
+            .grant_zome_call_capability(GrantZomeCallCapabilityPayload {
+                cell_id: self.cell_id,
+                cap_grant: cap_grant.clone(),
+            })
+            .await?;
+
+        Ok(cap_grant)
+    }
+}
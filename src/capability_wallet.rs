@@ -0,0 +1,118 @@
+use crate::util::{functions_cover, functions_width};
+use holo_hash::AgentPubKey;
+use holochain_zome_types::{
+    capability::{CapAccess, CapSecret, GrantedFunctions, ZomeCallCapGrant},
+    prelude::{FunctionName, ZomeName},
+};
+use parking_lot::RwLock;
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::Arc,
+};
+
+/// A capability secret received from another agent, with the functions that it was granted for.
+#[derive(Clone, PartialEq, Eq)]
+pub struct CapabilityClaim {
+    pub tag: String,
+    pub secret: CapSecret,
+    pub functions: GrantedFunctions,
+    /// The agents that an assigned grant was made to, or `None` if anyone with the secret can
+    /// use it.
+    pub assignees: Option<BTreeSet<AgentPubKey>>,
+}
+
+/// Custom debug implementation which won't attempt to print the `secret`
+impl std::fmt::Debug for CapabilityClaim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CapabilityClaim")
+            .field("tag", &self.tag)
+            .field("functions", &self.functions)
+            .field("assignees", &self.assignees)
+            .finish()
+    }
+}
+
+impl CapabilityClaim {
+    /// Create a claim for a grant that was made by another agent.
+    ///
+    /// Returns `None` for unrestricted grants, which do not need a secret.
+    pub fn from_grant(grant: &ZomeCallCapGrant) -> Option<Self> {
+        let (secret, assignees) = match &grant.access {
+            CapAccess::Unrestricted => return None,
+            CapAccess::Transferable { secret } => (*secret, None),
+            CapAccess::Assigned { secret, assignees } => (*secret, Some(assignees.clone())),
+        };
+
+        Some(Self {
+            tag: grant.tag.clone(),
+            secret,
+            functions: grant.functions.clone(),
+            assignees,
+        })
+    }
+
+    /// Whether a call with the given provenance can use this claim.
+    fn usable_by(&self, provenance: &AgentPubKey) -> bool {
+        self.assignees
+            .as_ref()
+            .is_none_or(|assignees| assignees.contains(provenance))
+    }
+}
+
+/// Holds capability claims received from other agents, keyed by the agent that granted them.
+///
+/// Use with [AppWebsocket::call_zome_with_claim](crate::AppWebsocket::call_zome_with_claim) to
+/// call zome functions in the cells of those agents.
+#[derive(Debug, Clone, Default)]
+pub struct CapabilityWallet {
+    claims: Arc<RwLock<HashMap<AgentPubKey, Vec<CapabilityClaim>>>>,
+}
+
+impl CapabilityWallet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a claim on a grant made by the given agent.
+    ///
+    /// This replaces any claim from the same agent with the same tag.
+    pub fn add_claim(&self, grantor: AgentPubKey, claim: CapabilityClaim) {
+        let mut claims = self.claims.write();
+        let grantor_claims = claims.entry(grantor).or_default();
+        grantor_claims.retain(|c| c.tag != claim.tag);
+        grantor_claims.push(claim);
+    }
+
+    /// Remove all claims on grants made by the given agent, and return them.
+    pub fn remove_claims(&self, grantor: &AgentPubKey) -> Vec<CapabilityClaim> {
+        self.claims.write().remove(grantor).unwrap_or_default()
+    }
+
+    /// The claims on grants made by the given agent.
+    pub fn claims(&self, grantor: &AgentPubKey) -> Vec<CapabilityClaim> {
+        self.claims.read().get(grantor).cloned().unwrap_or_default()
+    }
+
+    /// Get the secret to call a zome function of the given agent, using the narrowest claim which
+    /// covers the function.
+    ///
+    /// Claims on assigned grants are only used if `provenance` is one of their assignees, because
+    /// the grantor's conductor rejects calls made with them by any other agent.
+    pub fn get_cap_secret(
+        &self,
+        grantor: &AgentPubKey,
+        provenance: &AgentPubKey,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<CapSecret> {
+        self.claims
+            .read()
+            .get(grantor)?
+            .iter()
+            .filter(|c| {
+                c.usable_by(provenance) && functions_cover(&c.functions, zome_name, fn_name)
+            })
+            .min_by_key(|c| functions_width(&c.functions))
+            .map(|c| c.secret)
+    }
+}
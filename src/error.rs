@@ -1,4 +1,7 @@
+use crate::{PolicyViolation, SigningRejected, ZomeCallVerificationError};
+use holo_hash::AgentPubKey;
 use holochain_conductor_api::ExternalApiWireError;
+use holochain_zome_types::{cell::CellId, prelude::SerializedBytesError};
 use std::error::Error;
 
 #[derive(Debug, thiserror::Error)]
@@ -11,12 +14,40 @@ pub enum ConductorApiError {
     FreshNonceError(Box<dyn Error + Sync + Send>),
     #[error("Unable to sign zome call: {0}")]
     SignZomeCallError(String),
+    #[error("Zome call refused by signing policy: {0}")]
+    SigningPolicyViolation(PolicyViolation),
+    #[error("{0}")]
+    SigningRejected(SigningRejected),
+    #[error("Zome call failed verification: {0}")]
+    ZomeCallVerificationFailed(ZomeCallVerificationError),
+    #[error("Invalid capability grant: {0}")]
+    InvalidCapGrant(String),
+    #[error("No capability claim found for the zome function from {0}")]
+    CapClaimNotFound(AgentPubKey),
+    #[error("Credential rotation interval must be greater than zero")]
+    InvalidRotationInterval,
     #[error("Cell not found")]
     CellNotFound,
+    #[error("Multiple cells match the zome call target: {0:?}")]
+    AmbiguousCellTarget(Vec<CellId>),
     #[error("App not found")]
     AppNotFound,
+    #[error("Failed to enable app: {0:?}")]
+    EnableAppFailed(Vec<(CellId, String)>),
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
+    #[error("Credential store error: {0}")]
+    CredentialStoreError(String),
+    #[error("Audit log error: {0}")]
+    AuditLogError(String),
+    #[error("Keystore error: {0}")]
+    KeystoreError(String),
+    #[error("Remote signer error: {0}")]
+    RemoteSignerError(String),
+    #[error("Bundle error: {0}")]
+    BundleError(String),
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] SerializedBytesError),
 }
 
 pub type ConductorApiResult<T> = Result<T, ConductorApiError>;
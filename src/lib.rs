@@ -1,28 +1,71 @@
 mod admin_websocket;
 mod app_websocket;
 mod app_websocket_inner;
+mod bundle;
+mod cap_grant;
+mod capability_wallet;
 mod error;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod signing;
 mod util;
 
-pub use admin_websocket::{AdminWebsocket, AuthorizeSigningCredentialsPayload, EnableAppResponse};
+pub use admin_websocket::{
+    AdminWebsocket, AuthorizeSigningCredentialsPayload, ConnectAppOptions, ConnectedApp,
+    EnableAppResponse, InstallAndConnectOptions,
+};
+#[cfg(feature = "web_app")]
+pub use admin_websocket::{InstallWebAppPayload, InstalledWebApp};
 pub use app_websocket::{AppWebsocket, ZomeCallTarget};
+#[cfg(feature = "bundle_packing")]
+pub use bundle::packing::{pack_app_bundle, pack_dna_bundle};
+pub use bundle::{
+    inspect_app_bundle, inspect_bundle_file, inspect_dna_bundle, inspect_web_app_bundle,
+    AppBundleInfo, BundleInfo, DnaInfo, InspectOptions, RoleInfo, WebAppBundleInfo,
+};
+pub use cap_grant::CapGrantBuilder;
+pub use capability_wallet::{CapabilityClaim, CapabilityWallet};
 pub use error::{ConductorApiError, ConductorApiResult};
 pub use holochain_conductor_api::{
     AdminRequest, AdminResponse, AppAuthenticationRequest, AppAuthenticationToken,
     AppAuthenticationTokenIssued, AppInfo, AppRequest, AppResponse, AppStatusFilter, CellInfo,
-    IssueAppAuthenticationTokenPayload, ProvisionedCell,
+    IssueAppAuthenticationTokenPayload, ProvisionedCell, ZomeCallParamsSigned,
 };
 pub use holochain_types::{
-    app::{InstallAppPayload, InstalledAppId},
+    app::{AppBundleSource, InstallAppPayload, InstalledAppId},
     dna::AgentPubKey,
     websocket::AllowedOrigins,
 };
 pub use holochain_websocket::{ConnectRequest, WebsocketConfig};
 pub use holochain_zome_types::prelude::{
-    CellId, ClonedCell, ExternIO, GrantedFunctions, SerializedBytes, Timestamp,
+    CellId, ClonedCell, ExternIO, GrantedFunctions, SerializedBytes, Timestamp, ZomeCallParams,
+};
+#[cfg(feature = "metrics")]
+pub use metrics::{
+    clear_metrics_recorder, set_metrics_recorder, DynMetricsRecorder, MetricsRecorder,
+    PrometheusRecorder, RequestLabels,
+};
+pub use signing::approval_signing::{
+    ApprovalAgentSigner, ApprovalDecision, ApprovalRequest, SigningApprover, SigningRejected,
 };
+pub use signing::audit_signing::{
+    read_audit_log, verify_audit_log, AuditEntry, AuditLogKey, AuditLogSummary, AuditingAgentSigner,
+};
+pub use signing::auto_authorize_signing::AutoAuthorizingAgentSigner;
 pub use signing::client_signing::{ClientAgentSigner, SigningCredentials};
+pub use signing::credential_rotation::{
+    CredentialRotator, RotationHandle, RotationReport, SupersededGrant,
+};
+#[cfg(feature = "credential_store")]
+pub use signing::credential_store::CredentialStore;
 #[cfg(feature = "lair_signing")]
-pub use signing::lair_signing::LairAgentSigner;
+pub use signing::lair_signing::{LairAgentSigner, LairSigningCredentials};
+pub use signing::policy_signing::{PolicyAgentSigner, PolicyViolation, RateLimit, SigningPolicy};
+#[cfg(feature = "remote_signing")]
+pub use signing::remote_signing::{
+    RemoteAgentSigner, RemoteSignerDaemon, RemoteSignerEndpoint, RemoteSignerKey,
+};
+pub use signing::routing_signing::RoutingAgentSigner;
+pub use signing::seed_derivation::SigningKeySeed;
+pub use signing::signed_zome_call::{verify_zome_call, SignedZomeCall, ZomeCallVerificationError};
 pub use signing::{AgentSigner, DynAgentSigner};
@@ -0,0 +1,378 @@
+use holochain_conductor_api::{AdminRequest, AppRequest};
+use holochain_zome_types::{
+    prelude::{FunctionName, ZomeName},
+    zome_io::ZomeCallParams,
+};
+use parking_lot::{Mutex, RwLock};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub type DynMetricsRecorder = Arc<dyn MetricsRecorder + Send + Sync>;
+
+static RECORDER: RwLock<Option<DynMetricsRecorder>> = RwLock::new(None);
+
+/// Install a recorder which will receive metrics for every request sent by any
+/// [AdminWebsocket](crate::AdminWebsocket) or [AppWebsocket](crate::AppWebsocket), and every
+/// signal received by an app websocket.
+///
+/// Replaces any recorder that was previously installed.
+pub fn set_metrics_recorder(recorder: DynMetricsRecorder) {
+    *RECORDER.write() = Some(recorder);
+}
+
+/// Remove the installed recorder, if any. No further metrics will be recorded.
+pub fn clear_metrics_recorder() {
+    *RECORDER.write() = None;
+}
+
+/// Receives metrics recorded by the client.
+///
+/// Implement this to forward metrics to your own metrics system, or use the built-in
+/// [PrometheusRecorder].
+pub trait MetricsRecorder {
+    /// A request is about to be sent to the conductor.
+    fn request_started(&self, labels: &RequestLabels);
+
+    /// A request has completed, either with a response or an error.
+    ///
+    /// Every call to [MetricsRecorder::request_started] is followed by exactly one call to this
+    /// function with the same labels, including when the request future is dropped before it
+    /// completes. Dropped requests are reported as unsuccessful.
+    fn request_finished(&self, labels: &RequestLabels, duration: Duration, success: bool);
+
+    /// A signal has been received on an app websocket.
+    fn signal_received(&self, size_bytes: usize);
+}
+
+/// Identifies the kind of request that a metric was recorded for.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RequestLabels {
+    /// Either `admin` or `app`.
+    pub interface: &'static str,
+    /// The request type, such as `install_app` or `call_zome`.
+    pub request: &'static str,
+    /// The zome name, for zome calls only.
+    pub zome: Option<String>,
+    /// The function name, for zome calls only.
+    pub fn_name: Option<String>,
+}
+
+impl RequestLabels {
+    pub(crate) fn admin(request: &AdminRequest) -> Self {
+        Self {
+            interface: "admin",
+            request: crate::util::admin_request_kind(request),
+            zome: None,
+            fn_name: None,
+        }
+    }
+
+    /// Labels for an app request. `function` is the zome function being called, if it is known, so
+    /// that zome calls only need to be decoded when they were signed elsewhere.
+    pub(crate) fn app(request: &AppRequest, function: Option<(&ZomeName, &FunctionName)>) -> Self {
+        let (zome, fn_name) = match (request, function) {
+            (AppRequest::CallZome(_), Some((zome_name, fn_name))) => {
+                (Some(zome_name.to_string()), Some(fn_name.to_string()))
+            }
+            (AppRequest::CallZome(signed), None) => match signed.bytes.decode::<ZomeCallParams>() {
+                Ok(params) => (
+                    Some(params.zome_name.to_string()),
+                    Some(params.fn_name.to_string()),
+                ),
+                Err(_) => (None, None),
+            },
+            _ => (None, None),
+        };
+
+        Self {
+            interface: "app",
+            request: crate::util::app_request_kind(request),
+            zome,
+            fn_name,
+        }
+    }
+}
+
+/// Records a request with the installed recorder for as long as it is alive.
+///
+/// The request is reported as finished when this is dropped, and as successful only if
+/// [RequestTimer::succeeded] was called first.
+pub(crate) struct RequestTimer {
+    recorder: Option<(DynMetricsRecorder, RequestLabels)>,
+    started_at: Instant,
+    success: bool,
+}
+
+impl RequestTimer {
+    /// Start timing a request. The labels are only computed if a recorder is installed.
+    pub(crate) fn start(labels: impl FnOnce() -> RequestLabels) -> Self {
+        let recorder = RECORDER.read().clone().map(|recorder| {
+            let labels = labels();
+            recorder.request_started(&labels);
+            (recorder, labels)
+        });
+
+        Self {
+            recorder,
+            started_at: Instant::now(),
+            success: false,
+        }
+    }
+
+    pub(crate) fn succeeded(&mut self) {
+        self.success = true;
+    }
+}
+
+impl Drop for RequestTimer {
+    fn drop(&mut self) {
+        if let Some((recorder, labels)) = self.recorder.take() {
+            recorder.request_finished(&labels, self.started_at.elapsed(), self.success);
+        }
+    }
+}
+
+pub(crate) fn record_signal(size_bytes: usize) {
+    if let Some(recorder) = RECORDER.read().as_ref() {
+        recorder.signal_received(size_bytes);
+    }
+}
+
+/// Upper bounds, in seconds, of the request duration histogram buckets.
+const DURATION_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+#[derive(Default)]
+struct RequestStats {
+    in_flight: i64,
+    total: u64,
+    errors: u64,
+    duration_sum: f64,
+    duration_buckets: [u64; DURATION_BUCKETS.len()],
+}
+
+#[derive(Default)]
+struct PrometheusState {
+    requests: BTreeMap<RequestLabels, RequestStats>,
+    signals_total: u64,
+    signal_bytes_total: u64,
+}
+
+/// A [MetricsRecorder] which aggregates metrics in memory and renders them in the
+/// Prometheus text exposition format.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use holochain_client::{set_metrics_recorder, PrometheusRecorder};
+///
+/// let recorder = Arc::new(PrometheusRecorder::default());
+/// set_metrics_recorder(recorder.clone());
+///
+/// // Serve this from your metrics endpoint
+/// let text = recorder.render();
+/// ```
+#[derive(Default)]
+pub struct PrometheusRecorder {
+    state: Mutex<PrometheusState>,
+}
+
+impl PrometheusRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Render all recorded metrics in the Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        let state = self.state.lock();
+        let mut out = String::new();
+
+        write_header(
+            &mut out,
+            "holochain_client_requests_total",
+            "counter",
+            "Total number of completed requests sent to the conductor.",
+        );
+        for (labels, stats) in &state.requests {
+            write_sample(
+                &mut out,
+                "holochain_client_requests_total",
+                labels,
+                None,
+                stats.total,
+            );
+        }
+
+        write_header(
+            &mut out,
+            "holochain_client_request_errors_total",
+            "counter",
+            "Total number of requests that failed or were dropped before completing.",
+        );
+        for (labels, stats) in &state.requests {
+            write_sample(
+                &mut out,
+                "holochain_client_request_errors_total",
+                labels,
+                None,
+                stats.errors,
+            );
+        }
+
+        write_header(
+            &mut out,
+            "holochain_client_requests_in_flight",
+            "gauge",
+            "Number of requests currently awaiting a response.",
+        );
+        for (labels, stats) in &state.requests {
+            write_sample(
+                &mut out,
+                "holochain_client_requests_in_flight",
+                labels,
+                None,
+                stats.in_flight,
+            );
+        }
+
+        write_header(
+            &mut out,
+            "holochain_client_request_duration_seconds",
+            "histogram",
+            "Time taken for the conductor to respond to a request.",
+        );
+        for (labels, stats) in &state.requests {
+            let mut cumulative = 0;
+            for (bound, count) in DURATION_BUCKETS.iter().zip(stats.duration_buckets) {
+                cumulative += count;
+                write_sample(
+                    &mut out,
+                    "holochain_client_request_duration_seconds_bucket",
+                    labels,
+                    Some(&bound.to_string()),
+                    cumulative,
+                );
+            }
+            write_sample(
+                &mut out,
+                "holochain_client_request_duration_seconds_bucket",
+                labels,
+                Some("+Inf"),
+                stats.total,
+            );
+            write_sample(
+                &mut out,
+                "holochain_client_request_duration_seconds_sum",
+                labels,
+                None,
+                stats.duration_sum,
+            );
+            write_sample(
+                &mut out,
+                "holochain_client_request_duration_seconds_count",
+                labels,
+                None,
+                stats.total,
+            );
+        }
+
+        write_header(
+            &mut out,
+            "holochain_client_signals_received_total",
+            "counter",
+            "Total number of signals received on app websockets.",
+        );
+        let _ = writeln!(
+            out,
+            "holochain_client_signals_received_total {}",
+            state.signals_total
+        );
+
+        write_header(
+            &mut out,
+            "holochain_client_signal_bytes_received_total",
+            "counter",
+            "Total size in bytes of signals received on app websockets.",
+        );
+        let _ = writeln!(
+            out,
+            "holochain_client_signal_bytes_received_total {}",
+            state.signal_bytes_total
+        );
+
+        out
+    }
+}
+
+impl MetricsRecorder for PrometheusRecorder {
+    fn request_started(&self, labels: &RequestLabels) {
+        self.state
+            .lock()
+            .requests
+            .entry(labels.clone())
+            .or_default()
+            .in_flight += 1;
+    }
+
+    fn request_finished(&self, labels: &RequestLabels, duration: Duration, success: bool) {
+        let mut state = self.state.lock();
+        let stats = state.requests.entry(labels.clone()).or_default();
+
+        let seconds = duration.as_secs_f64();
+        stats.in_flight -= 1;
+        stats.total += 1;
+        if !success {
+            stats.errors += 1;
+        }
+        stats.duration_sum += seconds;
+        if let Some(bucket) = DURATION_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            stats.duration_buckets[bucket] += 1;
+        }
+    }
+
+    fn signal_received(&self, size_bytes: usize) {
+        let mut state = self.state.lock();
+        state.signals_total += 1;
+        state.signal_bytes_total += size_bytes as u64;
+    }
+}
+
+fn write_header(out: &mut String, name: &str, metric_type: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {metric_type}");
+}
+
+fn write_sample(
+    out: &mut String,
+    name: &str,
+    labels: &RequestLabels,
+    le: Option<&str>,
+    value: impl std::fmt::Display,
+) {
+    let _ = write!(
+        out,
+        "{name}{{interface=\"{}\",request=\"{}\"",
+        labels.interface, labels.request
+    );
+    if let Some(zome) = &labels.zome {
+        let _ = write!(out, ",zome=\"{}\"", escape_label_value(zome));
+    }
+    if let Some(fn_name) = &labels.fn_name {
+        let _ = write!(out, ",fn_name=\"{}\"", escape_label_value(fn_name));
+    }
+    if let Some(le) = le {
+        let _ = write!(out, ",le=\"{le}\"");
+    }
+    let _ = writeln!(out, "}} {value}");
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -1,25 +1,73 @@
 use std::sync::Arc;
 
+use crate::ConductorApiError;
 use anyhow::Result;
 use async_trait::async_trait;
 use holo_hash::AgentPubKey;
-use holochain_conductor_api::ZomeCallParamsSigned;
+use holochain_conductor_api::{AppInfo, ZomeCallParamsSigned};
 use holochain_zome_types::{
     capability::CapSecret,
     cell::CellId,
     dependencies::holochain_integrity_types::Signature,
+    prelude::{FunctionName, ZomeName},
     zome_io::{ExternIO, ZomeCallParams},
 };
 
+pub(crate) mod approval_signing;
+
+pub(crate) mod audit_signing;
+
+pub(crate) mod auto_authorize_signing;
+
 pub(crate) mod client_signing;
 
+pub(crate) mod credential_rotation;
+
+#[cfg(feature = "credential_store")]
+pub(crate) mod credential_store;
+
 #[cfg(feature = "lair_signing")]
 pub(crate) mod lair_signing;
 
+pub(crate) mod policy_signing;
+
+#[cfg(feature = "remote_signing")]
+pub(crate) mod remote_signing;
+
+pub(crate) mod routing_signing;
+
+pub(crate) mod seed_derivation;
+
+pub(crate) mod signed_zome_call;
+
 pub type DynAgentSigner = Arc<dyn AgentSigner + Send + Sync>;
 
 #[async_trait]
 pub trait AgentSigner {
+    /// Prepare to sign a zome call for the given `cell_id`.
+    ///
+    /// This is called before a zome call is made, before any other function of the signer, so
+    /// that signers which need to do async work, such as fetching credentials, can do it here.
+    /// The default implementation does nothing.
+    async fn prepare(&self, _cell_id: &CellId) -> Result<()> {
+        Ok(())
+    }
+
+    /// Prepare to sign a zome call to the given function of the given `cell_id`.
+    ///
+    /// This is called instead of [AgentSigner::prepare] when the function being called is known,
+    /// so that signers which hold credentials scoped to particular functions can make sure that
+    /// the function is covered. Signers which wrap another signer should delegate to this function
+    /// of the inner signer. The default implementation uses [AgentSigner::prepare].
+    async fn prepare_for_fn(
+        &self,
+        cell_id: &CellId,
+        _zome_name: &ZomeName,
+        _fn_name: &FunctionName,
+    ) -> Result<()> {
+        self.prepare(cell_id).await
+    }
+
     /// Sign the given data with the public key found in the agent id of the provenance.
     async fn sign(
         &self,
@@ -28,25 +76,137 @@ pub trait AgentSigner {
         data_to_sign: Arc<[u8]>,
     ) -> Result<Signature>;
 
+    /// Sign a zome call, given the zome call params that `data_to_sign` is the hash of.
+    ///
+    /// `signing_cell_id` is the cell whose credentials sign the call. This is the cell being
+    /// called, except for calls to the cells of other agents, which are signed with the credentials
+    /// for our own cell of the same DNA.
+    ///
+    /// This is used to sign all zome calls, so signers which need to know what they are signing,
+    /// such as to enforce rules about which functions can be called, should override this. Signers
+    /// which wrap another signer should delegate to this function of the inner signer. The default
+    /// implementation uses [AgentSigner::sign].
+    async fn sign_zome_call_params(
+        &self,
+        signing_cell_id: &CellId,
+        params: &ZomeCallParams,
+        data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        self.sign(signing_cell_id, params.provenance.clone(), data_to_sign)
+            .await
+    }
+
     fn get_provenance(&self, cell_id: &CellId) -> Option<AgentPubKey>;
 
     /// Get the capability secret for the given `cell_id` if it exists.
     fn get_cap_secret(&self, cell_id: &CellId) -> Option<CapSecret>;
+
+    /// Get the provenance to use for a call to the given zome function.
+    ///
+    /// Signers which hold credentials scoped to particular functions should override this. The
+    /// default implementation uses [AgentSigner::get_provenance].
+    fn get_provenance_for_fn(
+        &self,
+        cell_id: &CellId,
+        _zome_name: &ZomeName,
+        _fn_name: &FunctionName,
+    ) -> Option<AgentPubKey> {
+        self.get_provenance(cell_id)
+    }
+
+    /// Get the capability secret to use for a call to the given zome function, if it exists.
+    ///
+    /// Signers which hold credentials scoped to particular functions should override this. The
+    /// default implementation uses [AgentSigner::get_cap_secret].
+    fn get_cap_secret_for_fn(
+        &self,
+        cell_id: &CellId,
+        _zome_name: &ZomeName,
+        _fn_name: &FunctionName,
+    ) -> Option<CapSecret> {
+        self.get_cap_secret(cell_id)
+    }
+
+    /// Get the provenance and capability secret to use for a call to the given zome function,
+    /// both from the same credentials.
+    ///
+    /// Signers whose credentials can be replaced while zome calls are being made, such as by a
+    /// [CredentialRotator](crate::CredentialRotator), should override this to look both up at
+    /// once, so that a call never pairs the provenance of one set of credentials with the cap
+    /// secret of another. Signers which wrap another signer should delegate to this function of
+    /// the inner signer. The default implementation uses [AgentSigner::get_provenance_for_fn] and
+    /// [AgentSigner::get_cap_secret_for_fn].
+    fn get_credentials_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<(AgentPubKey, Option<CapSecret>)> {
+        Some((
+            self.get_provenance_for_fn(cell_id, zome_name, fn_name)?,
+            self.get_cap_secret_for_fn(cell_id, zome_name, fn_name),
+        ))
+    }
+
+    /// Called by [AppWebsocket](crate::AppWebsocket) with the latest [AppInfo] when it connects
+    /// and whenever it refreshes its cached app info, such as after creating clone cells.
+    ///
+    /// Signers which choose credentials by role, such as
+    /// [RoutingAgentSigner](crate::RoutingAgentSigner), should override this to stay up to date
+    /// with the cells of the app. Signers which wrap another signer should delegate to this
+    /// function of the inner signer. The default implementation does nothing.
+    fn update_app_info(&self, _app_info: &AppInfo) {}
 }
 
-/// Signs an unsigned zome call using the provided signing implementation
+/// Signs an unsigned zome call using the provided signing implementation, with the credentials for
+/// `signing_cell_id`
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        skip_all,
+        err,
+        fields(
+            cell_id = ?params.cell_id,
+            zome = %params.zome_name,
+            fn_name = %params.fn_name,
+        )
+    )
+)]
 pub(crate) async fn sign_zome_call(
+    signing_cell_id: &CellId,
     params: ZomeCallParams,
     signer: DynAgentSigner,
 ) -> Result<ZomeCallParamsSigned> {
-    let pub_key = params.provenance.clone();
     let (bytes, bytes_hash) = params.serialize_and_hash()?;
+
+    #[cfg(feature = "tracing")]
+    let started_at = std::time::Instant::now();
+
     let signature = signer
-        .sign(&params.cell_id, pub_key, bytes_hash.into())
+        .sign_zome_call_params(signing_cell_id, &params, bytes_hash.into())
         .await?;
 
+    #[cfg(feature = "tracing")]
+    tracing::debug!(
+        elapsed_us = started_at.elapsed().as_micros() as u64,
+        "Signed zome call"
+    );
+
     Ok(ZomeCallParamsSigned {
         bytes: ExternIO(bytes),
         signature,
     })
 }
+
+/// Convert an error from signing a zome call into a [ConductorApiError], keeping the reason if a
+/// signer refused to sign.
+pub(crate) fn sign_error(error: anyhow::Error) -> ConductorApiError {
+    let error = match error.downcast::<policy_signing::PolicyViolation>() {
+        Ok(violation) => return ConductorApiError::SigningPolicyViolation(violation),
+        Err(error) => error,
+    };
+    match error.downcast::<approval_signing::SigningRejected>() {
+        Ok(rejected) => ConductorApiError::SigningRejected(rejected),
+        Err(error) => ConductorApiError::SignZomeCallError(error.to_string()),
+    }
+}
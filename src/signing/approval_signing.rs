@@ -0,0 +1,545 @@
+use super::{AgentSigner, DynAgentSigner};
+use anyhow::Result;
+use async_trait::async_trait;
+use holo_hash::AgentPubKey;
+use holochain_conductor_api::AppInfo;
+use holochain_zome_types::{
+    capability::CapSecret,
+    cell::CellId,
+    dependencies::holochain_integrity_types::Signature,
+    prelude::{FunctionName, Timestamp, ZomeName},
+    zome_io::ZomeCallParams,
+};
+use parking_lot::Mutex;
+use serde::{
+    de::{DeserializeSeed, IgnoredAny, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+use std::{
+    collections::HashMap,
+    fmt::Write,
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// A zome call that is waiting to be approved.
+#[derive(Debug, Clone)]
+pub struct ApprovalRequest {
+    pub cell_id: CellId,
+    pub zome_name: ZomeName,
+    pub fn_name: FunctionName,
+    /// A human readable rendering of the decoded payload, or its size if it could not be decoded.
+    ///
+    /// The preview is limited in length and in how deeply nested values are shown, and byte strings
+    /// are shown as their length and the hex of their first few bytes.
+    pub payload_preview: String,
+    pub payload_bytes: usize,
+    pub expires_at: Timestamp,
+}
+
+/// The decision made about an [ApprovalRequest].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApprovalDecision {
+    /// Sign this zome call.
+    Approve,
+    /// Sign this zome call, and all calls to the same function of the same cell for the given
+    /// duration without asking again.
+    ApproveFor(Duration),
+    /// Refuse to sign this zome call.
+    Reject,
+    /// Refuse to sign this zome call, and all calls to the same function of the same cell for the
+    /// given duration without asking again.
+    RejectFor(Duration),
+}
+
+/// Decides whether zome calls should be signed, usually by asking the user.
+///
+/// This is implemented for async functions and closures which take an [ApprovalRequest] and return
+/// an [ApprovalDecision].
+#[async_trait]
+pub trait SigningApprover {
+    async fn approve(&self, request: ApprovalRequest) -> ApprovalDecision;
+}
+
+#[async_trait]
+impl<F, Fut> SigningApprover for F
+where
+    F: Fn(ApprovalRequest) -> Fut + Send + Sync,
+    Fut: Future<Output = ApprovalDecision> + Send,
+{
+    async fn approve(&self, request: ApprovalRequest) -> ApprovalDecision {
+        self(request).await
+    }
+}
+
+/// The error returned when a zome call was not approved for signing.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Signing {zome_name}::{fn_name} was rejected for cell {cell_id:?}")]
+pub struct SigningRejected {
+    pub cell_id: CellId,
+    pub zome_name: ZomeName,
+    pub fn_name: FunctionName,
+}
+
+type FunctionKey = (CellId, ZomeName, FunctionName);
+
+/// The remembered decision for a function, locked while its approver is being asked.
+type DecisionSlot = Arc<tokio::sync::Mutex<Option<(bool, Instant)>>>;
+
+/// An [AgentSigner] which asks a [SigningApprover] to approve each zome call before delegating to
+/// an inner signer, similar to a wallet.
+///
+/// Decisions which are remembered for a function, with [ApprovalDecision::ApproveFor] or
+/// [ApprovalDecision::RejectFor], are applied to calls to that function until they expire. Only
+/// one approval is requested at a time for each function, so concurrent calls to the same
+/// function only ask once, while calls to other functions are not held up. Expired decisions are
+/// forgotten, so only the decisions which still apply are held.
+///
+/// Rejected calls are returned from [AppWebsocket::call_zome](crate::AppWebsocket::call_zome) as
+/// [ConductorApiError::SigningRejected](crate::ConductorApiError::SigningRejected).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use holochain_client::{
+///     ApprovalAgentSigner, ApprovalDecision, ApprovalRequest, ClientAgentSigner,
+/// };
+///
+/// let signer = ApprovalAgentSigner::new(
+///     ClientAgentSigner::default().into(),
+///     |request: ApprovalRequest| async move {
+///         println!(
+///             "Approve {}::{} with {}?",
+///             request.zome_name, request.fn_name, request.payload_preview
+///         );
+///         ApprovalDecision::ApproveFor(Duration::from_secs(60))
+///     },
+/// );
+/// ```
+#[derive(Clone)]
+pub struct ApprovalAgentSigner {
+    inner: DynAgentSigner,
+    approver: Arc<dyn SigningApprover + Send + Sync>,
+    remembered: Arc<Mutex<HashMap<FunctionKey, DecisionSlot>>>,
+}
+
+impl std::fmt::Debug for ApprovalAgentSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ApprovalAgentSigner").finish()
+    }
+}
+
+impl ApprovalAgentSigner {
+    pub fn new(
+        inner: DynAgentSigner,
+        approver: impl SigningApprover + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            inner,
+            approver: Arc::new(approver),
+            remembered: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Forget all remembered decisions, so that every function is asked about again.
+    pub fn forget_decisions(&self) {
+        self.remembered.lock().clear();
+    }
+
+    async fn is_approved(&self, params: &ZomeCallParams) -> bool {
+        let key = (
+            params.cell_id.clone(),
+            params.zome_name.clone(),
+            params.fn_name.clone(),
+        );
+
+        let slot = {
+            let mut remembered = self.remembered.lock();
+            forget_expired(&mut remembered);
+            remembered.entry(key).or_default().clone()
+        };
+
+        // Held while asking, so that only one approval is requested at a time for the function
+        let mut remembered = slot.lock().await;
+        match *remembered {
+            Some((approved, until)) if until > Instant::now() => return approved,
+            Some(_) => *remembered = None,
+            None => {}
+        }
+
+        let decision = self.approver.approve(approval_request(params)).await;
+        let (approved, remember_for) = match decision {
+            ApprovalDecision::Approve => (true, None),
+            ApprovalDecision::ApproveFor(duration) => (true, Some(duration)),
+            ApprovalDecision::Reject => (false, None),
+            ApprovalDecision::RejectFor(duration) => (false, Some(duration)),
+        };
+        if let Some(until) = remember_for.and_then(|d| Instant::now().checked_add(d)) {
+            *remembered = Some((approved, until));
+        }
+
+        approved
+    }
+}
+
+/// Drop the decisions which have expired, or were never remembered, so that the map only holds
+/// decisions which still apply and functions which calls are waiting on.
+fn forget_expired(remembered: &mut HashMap<FunctionKey, DecisionSlot>) {
+    let now = Instant::now();
+    remembered.retain(|_, slot| {
+        // Slots held elsewhere belong to calls which are waiting for, or asking for, a decision
+        Arc::strong_count(slot) > 1
+            || slot
+                .try_lock()
+                .is_ok_and(|decision| decision.is_some_and(|(_, until)| until > now))
+    });
+}
+
+fn approval_request(params: &ZomeCallParams) -> ApprovalRequest {
+    let payload_bytes = params.payload.0.len();
+    let payload_preview = match params.payload.decode::<PreviewValue>() {
+        Ok(value) => {
+            let mut preview = TruncatingWriter::default();
+            if write!(preview, "{value}").is_err() {
+                preview.out.push_str("...");
+            }
+            preview.out
+        }
+        Err(_) => format!("<{payload_bytes} bytes>"),
+    };
+
+    ApprovalRequest {
+        cell_id: params.cell_id.clone(),
+        zome_name: params.zome_name.clone(),
+        fn_name: params.fn_name.clone(),
+        payload_preview,
+        payload_bytes,
+        expires_at: params.expires_at,
+    }
+}
+
+/// The longest preview, in characters, that is shown to the approver.
+const MAX_PREVIEW_CHARS: usize = 1024;
+
+/// How deeply nested arrays and maps are shown in a preview.
+const MAX_PREVIEW_DEPTH: usize = 8;
+
+/// How many elements of an array or entries of a map are shown in a preview.
+const MAX_PREVIEW_ITEMS: usize = 32;
+
+/// How many leading bytes of a byte string are shown in a preview.
+const PREVIEW_BYTES_PREFIX: usize = 8;
+
+/// Collects up to [MAX_PREVIEW_CHARS] characters, then fails so that formatting stops early.
+#[derive(Default)]
+struct TruncatingWriter {
+    out: String,
+    chars: usize,
+}
+
+impl std::fmt::Write for TruncatingWriter {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        for c in s.chars() {
+            if self.chars == MAX_PREVIEW_CHARS {
+                return Err(std::fmt::Error);
+            }
+            self.out.push(c);
+            self.chars += 1;
+        }
+        Ok(())
+    }
+}
+
+/// Any MessagePack value, decoded so that a payload can be shown to the approver without knowing
+/// its type.
+///
+/// Only the start of byte strings, and the first [MAX_PREVIEW_ITEMS] items of arrays and maps up to
+/// [MAX_PREVIEW_DEPTH] levels deep, are kept.
+#[derive(Debug)]
+enum PreviewValue {
+    Nil,
+    Bool(bool),
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+    String(String),
+    Bytes {
+        len: usize,
+        prefix: Vec<u8>,
+    },
+    Array {
+        values: Vec<PreviewValue>,
+        more: bool,
+    },
+    Map {
+        entries: Vec<(PreviewValue, PreviewValue)>,
+        more: bool,
+    },
+}
+
+impl std::fmt::Display for PreviewValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PreviewValue::Nil => f.write_str("nil"),
+            PreviewValue::Bool(value) => write!(f, "{value}"),
+            PreviewValue::Int(value) => write!(f, "{value}"),
+            PreviewValue::UInt(value) => write!(f, "{value}"),
+            PreviewValue::Float(value) => write!(f, "{value}"),
+            PreviewValue::String(value) => write!(f, "{value:?}"),
+            PreviewValue::Bytes { len, prefix } => {
+                write!(f, "<{len} bytes: ")?;
+                for byte in prefix {
+                    write!(f, "{byte:02x}")?;
+                }
+                if prefix.len() < *len {
+                    f.write_str("...")?;
+                }
+                f.write_str(">")
+            }
+            PreviewValue::Array { values, more } => {
+                f.write_str("[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{value}")?;
+                }
+                write_more(f, !values.is_empty(), *more)?;
+                f.write_str("]")
+            }
+            PreviewValue::Map { entries, more } => {
+                f.write_str("{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{key}: {value}")?;
+                }
+                write_more(f, !entries.is_empty(), *more)?;
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Mark that items of an array or map were left out of the preview.
+fn write_more(f: &mut std::fmt::Formatter<'_>, after_items: bool, more: bool) -> std::fmt::Result {
+    match (after_items, more) {
+        (true, true) => f.write_str(", ..."),
+        (false, true) => f.write_str("..."),
+        (_, false) => Ok(()),
+    }
+}
+
+impl<'de> Deserialize<'de> for PreviewValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        PreviewVisitor { depth: 0 }.deserialize(deserializer)
+    }
+}
+
+/// Decodes a [PreviewValue] nested `depth` arrays or maps deep.
+#[derive(Clone, Copy)]
+struct PreviewVisitor {
+    depth: usize,
+}
+
+impl PreviewVisitor {
+    fn nested(self) -> Self {
+        Self {
+            depth: self.depth + 1,
+        }
+    }
+}
+
+impl<'de> DeserializeSeed<'de> for PreviewVisitor {
+    type Value = PreviewValue;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for PreviewVisitor {
+    type Value = PreviewValue;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("any MessagePack value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Self::Value, E> {
+        Ok(PreviewValue::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Self::Value, E> {
+        Ok(PreviewValue::Int(value))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Self::Value, E> {
+        Ok(PreviewValue::UInt(value))
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Self::Value, E> {
+        Ok(PreviewValue::Float(value))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E> {
+        // Longer strings would be truncated when the preview is written anyway
+        Ok(PreviewValue::String(
+            value.chars().take(MAX_PREVIEW_CHARS).collect(),
+        ))
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E> {
+        Ok(PreviewValue::Bytes {
+            len: value.len(),
+            prefix: value.iter().take(PREVIEW_BYTES_PREFIX).copied().collect(),
+        })
+    }
+
+    fn visit_unit<E>(self) -> Result<Self::Value, E> {
+        Ok(PreviewValue::Nil)
+    }
+
+    fn visit_none<E>(self) -> Result<Self::Value, E> {
+        Ok(PreviewValue::Nil)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        self.deserialize(deserializer)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = Vec::new();
+        let mut more = false;
+        loop {
+            if self.depth == MAX_PREVIEW_DEPTH || values.len() == MAX_PREVIEW_ITEMS {
+                while seq.next_element::<IgnoredAny>()?.is_some() {
+                    more = true;
+                }
+                break;
+            }
+            match seq.next_element_seed(self.nested())? {
+                Some(value) => values.push(value),
+                None => break,
+            }
+        }
+        Ok(PreviewValue::Array { values, more })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut entries = Vec::new();
+        let mut more = false;
+        loop {
+            if self.depth == MAX_PREVIEW_DEPTH || entries.len() == MAX_PREVIEW_ITEMS {
+                while map.next_entry::<IgnoredAny, IgnoredAny>()?.is_some() {
+                    more = true;
+                }
+                break;
+            }
+            match map.next_entry_seed(self.nested(), self.nested())? {
+                Some(entry) => entries.push(entry),
+                None => break,
+            }
+        }
+        Ok(PreviewValue::Map { entries, more })
+    }
+}
+
+#[async_trait]
+impl AgentSigner for ApprovalAgentSigner {
+    async fn prepare(&self, cell_id: &CellId) -> Result<()> {
+        self.inner.prepare(cell_id).await
+    }
+
+    async fn prepare_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Result<()> {
+        self.inner.prepare_for_fn(cell_id, zome_name, fn_name).await
+    }
+
+    /// Refuses to sign, because the user cannot be shown what they are approving without the zome
+    /// call.
+    async fn sign(
+        &self,
+        _cell_id: &CellId,
+        _provenance: AgentPubKey,
+        _data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        Err(anyhow::anyhow!(
+            "Only zome calls can be signed with an approval signer"
+        ))
+    }
+
+    async fn sign_zome_call_params(
+        &self,
+        signing_cell_id: &CellId,
+        params: &ZomeCallParams,
+        data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        if !self.is_approved(params).await {
+            return Err(SigningRejected {
+                cell_id: params.cell_id.clone(),
+                zome_name: params.zome_name.clone(),
+                fn_name: params.fn_name.clone(),
+            }
+            .into());
+        }
+
+        self.inner
+            .sign_zome_call_params(signing_cell_id, params, data_to_sign)
+            .await
+    }
+
+    fn get_provenance(&self, cell_id: &CellId) -> Option<AgentPubKey> {
+        self.inner.get_provenance(cell_id)
+    }
+
+    fn get_cap_secret(&self, cell_id: &CellId) -> Option<CapSecret> {
+        self.inner.get_cap_secret(cell_id)
+    }
+
+    fn get_provenance_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<AgentPubKey> {
+        self.inner
+            .get_provenance_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn get_cap_secret_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<CapSecret> {
+        self.inner
+            .get_cap_secret_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn get_credentials_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<(AgentPubKey, Option<CapSecret>)> {
+        self.inner
+            .get_credentials_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn update_app_info(&self, app_info: &AppInfo) {
+        self.inner.update_app_info(app_info)
+    }
+}
+
+impl From<ApprovalAgentSigner> for DynAgentSigner {
+    fn from(signer: ApprovalAgentSigner) -> Self {
+        Arc::new(signer)
+    }
+}
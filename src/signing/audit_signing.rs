@@ -0,0 +1,533 @@
+use super::{AgentSigner, DynAgentSigner};
+use crate::{ConductorApiError, ConductorApiResult};
+use anyhow::Result;
+use async_trait::async_trait;
+use holo_hash::AgentPubKey;
+use holochain_conductor_api::AppInfo;
+use holochain_nonce::Nonce256Bits;
+use holochain_zome_types::{
+    capability::CapSecret,
+    cell::CellId,
+    dependencies::holochain_integrity_types::Signature,
+    prelude::{FunctionName, Timestamp, ZomeName},
+    zome_io::{ExternIO, ZomeCallParams},
+};
+use parking_lot::Mutex;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{File, TryLockError},
+    io::{BufReader, Read, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+const HASH_BYTES: usize = 32;
+const ENTRY_HASH_PERSONAL: &[u8] = b"hc_client_audit";
+const MAX_ENTRY_BYTES: usize = 64 * 1024;
+
+/// The secret key that the hashes of an audit log are computed with.
+///
+/// Without the key, someone who can write to the log cannot compute valid hashes for entries that
+/// they modify or add, so keep it somewhere other than next to the log.
+#[derive(Clone)]
+pub struct AuditLogKey([u8; 32]);
+
+/// Custom debug implementation which won't attempt to print the key
+impl std::fmt::Debug for AuditLogKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditLogKey").finish()
+    }
+}
+
+impl AuditLogKey {
+    /// Use the given bytes as the key.
+    pub fn from_bytes(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    /// Generate a new random key.
+    pub fn generate() -> Self {
+        let mut key = [0; 32];
+        OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    /// The bytes of the key, so that it can be stored.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+/// A record of a zome call that was signed by an [AuditingAgentSigner].
+///
+/// Each entry includes the hash of the entry before it, so that entries cannot be removed, reordered
+/// or modified without breaking the chain.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// The position of the entry in the log, starting from 0.
+    pub seq: u64,
+    /// When the zome call was signed.
+    pub timestamp: Timestamp,
+    pub cell_id: CellId,
+    pub provenance: AgentPubKey,
+    pub zome_name: ZomeName,
+    pub fn_name: FunctionName,
+    /// The BLAKE2b-256 hash of the zome call payload.
+    pub payload_hash: [u8; HASH_BYTES],
+    pub nonce: Nonce256Bits,
+    pub expires_at: Timestamp,
+    pub signature: Signature,
+    /// The hash of the previous entry, or all zeros for the first entry.
+    pub prev_hash: [u8; HASH_BYTES],
+    /// The keyed hash of this entry, covering every other field.
+    pub hash: [u8; HASH_BYTES],
+}
+
+impl AuditEntry {
+    fn compute_hash(&self, key: &AuditLogKey) -> ConductorApiResult<[u8; HASH_BYTES]> {
+        let fields = ExternIO::encode((
+            self.seq,
+            &self.timestamp,
+            &self.cell_id,
+            &self.provenance,
+            &self.zome_name,
+            &self.fn_name,
+            &self.payload_hash,
+            &self.nonce,
+            &self.expires_at,
+            &self.signature,
+            &self.prev_hash,
+        ))?;
+
+        let mut hash = [0; HASH_BYTES];
+        hash.copy_from_slice(
+            blake2b_simd::Params::new()
+                .hash_length(HASH_BYTES)
+                .key(&key.0)
+                .personal(ENTRY_HASH_PERSONAL)
+                .hash(&fields.0)
+                .as_bytes(),
+        );
+        Ok(hash)
+    }
+}
+
+/// The result of successfully verifying an audit log with [verify_audit_log].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditLogSummary {
+    /// The number of entries in the log.
+    pub entries: u64,
+    /// The hash of the last entry, or all zeros if the log is empty.
+    ///
+    /// Removing entries from the end of the log cannot be detected from the log alone. Keep a copy
+    /// of the head hash somewhere else and compare it to detect that.
+    pub head_hash: [u8; HASH_BYTES],
+}
+
+struct AuditLogWriter {
+    file: File,
+    /// The length of the file up to the end of the last entry that was written completely.
+    len: u64,
+    next_seq: u64,
+    head_hash: [u8; HASH_BYTES],
+}
+
+/// The entries read from an audit log.
+struct AuditLogContents {
+    entries: Vec<AuditEntry>,
+    /// The length of the log up to the end of the last complete entry.
+    len: u64,
+    /// Whether the log ends with an entry that was not completely written.
+    truncated: bool,
+}
+
+/// An [AgentSigner] which appends an [AuditEntry] to a hash-chained log file for every zome call
+/// that it signs with an inner signer.
+///
+/// Entries are written and synced to disk after the inner signer succeeds and before the signature
+/// is returned, so every signature that leaves the signer has been recorded. Use
+/// [verify_audit_log] to check that a log has not been tampered with, and [read_audit_log] to
+/// read its entries.
+///
+/// The hashes are keyed with an [AuditLogKey], so that only holders of the key can produce a log
+/// which verifies. Without the key, anyone who can write to the log could rewrite it and compute
+/// new hashes.
+///
+/// Only zome calls can be signed, because other data could not be recorded meaningfully. A zome
+/// call whose entry would be larger than the largest entry that can be read back, for example
+/// because of a very long function name, is refused rather than signed.
+///
+/// The log file is locked while the signer, or any clone of it, is open, so only one signer can
+/// write to a log at a time. Other processes that do not take the lock must not write to the log,
+/// because entries written by two writers would break the hash chain. On some platforms, such as
+/// Windows, the lock also prevents the log from being read by [verify_audit_log] and
+/// [read_audit_log] until the signer is dropped.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use holochain_client::{AuditLogKey, AuditingAgentSigner, ClientAgentSigner};
+///
+/// let key = AuditLogKey::from_bytes([0; 32]);
+/// let signer =
+///     AuditingAgentSigner::open("./audit.log", key, ClientAgentSigner::default().into()).unwrap();
+/// ```
+#[derive(Clone)]
+pub struct AuditingAgentSigner {
+    inner: DynAgentSigner,
+    path: PathBuf,
+    key: AuditLogKey,
+    writer: Arc<Mutex<AuditLogWriter>>,
+}
+
+impl std::fmt::Debug for AuditingAgentSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuditingAgentSigner")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl AuditingAgentSigner {
+    /// Open the audit log at the given path, creating it if it does not exist, and sign with the
+    /// given inner signer.
+    ///
+    /// An existing log is verified with the key before new entries are added to it, and an error
+    /// is returned if it has been tampered with. If the last entry of the log was not completely
+    /// written, for example because the process stopped while writing it, it is removed.
+    ///
+    /// An error is also returned if the log is already open in another signer.
+    pub fn open(
+        path: impl Into<PathBuf>,
+        key: AuditLogKey,
+        inner: DynAgentSigner,
+    ) -> ConductorApiResult<Self> {
+        let path = path.into();
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)?;
+        file.try_lock().map_err(|e| match e {
+            TryLockError::WouldBlock => audit_log_error(format!(
+                "Audit log is already open in another signer: {}",
+                path.display()
+            )),
+            TryLockError::Error(e) => e.into(),
+        })?;
+
+        // Read through the locked file, because on some platforms the lock prevents other handles
+        // from reading it
+        let contents = read_audit_log_contents(&file)?;
+        let summary = verify_entries(&contents.entries, &key)?;
+        if contents.truncated {
+            #[cfg(feature = "tracing")]
+            tracing::warn!(path = ?path, "Removing incomplete entry from the end of the audit log");
+
+            file.set_len(contents.len)?;
+        }
+
+        Ok(Self {
+            inner,
+            path,
+            key,
+            writer: Arc::new(Mutex::new(AuditLogWriter {
+                file,
+                len: contents.len,
+                next_seq: summary.entries,
+                head_hash: summary.head_hash,
+            })),
+        })
+    }
+
+    /// The path of the audit log.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry for a signed zome call to the log, on a blocking thread so that the write
+    /// and sync don't block the async runtime.
+    async fn record(
+        &self,
+        params: &ZomeCallParams,
+        signature: &Signature,
+    ) -> ConductorApiResult<()> {
+        let mut payload_hash = [0; HASH_BYTES];
+        payload_hash.copy_from_slice(
+            blake2b_simd::Params::new()
+                .hash_length(HASH_BYTES)
+                .hash(&params.payload.0)
+                .as_bytes(),
+        );
+        let mut entry = AuditEntry {
+            seq: 0,
+            timestamp: Timestamp::now(),
+            cell_id: params.cell_id.clone(),
+            provenance: params.provenance.clone(),
+            zome_name: params.zome_name.clone(),
+            fn_name: params.fn_name.clone(),
+            payload_hash,
+            nonce: params.nonce,
+            expires_at: params.expires_at,
+            signature: signature.clone(),
+            prev_hash: [0; HASH_BYTES],
+            hash: [0; HASH_BYTES],
+        };
+
+        let key = self.key.clone();
+        let writer = self.writer.clone();
+        tokio::task::spawn_blocking(move || -> ConductorApiResult<()> {
+            let mut writer = writer.lock();
+            entry.seq = writer.next_seq;
+            entry.prev_hash = writer.head_hash;
+            entry.hash = entry.compute_hash(&key)?;
+
+            let bytes = ExternIO::encode(&entry)?.0;
+            if bytes.len() > MAX_ENTRY_BYTES {
+                // The entry could be written, but the log could not be read or opened again
+                return Err(audit_log_error(format!(
+                    "Entry {} is too large, at {} bytes",
+                    entry.seq,
+                    bytes.len()
+                )));
+            }
+            let mut frame = Vec::with_capacity(4 + bytes.len());
+            frame.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            frame.extend_from_slice(&bytes);
+            let written = writer
+                .file
+                .write_all(&frame)
+                .and_then(|_| writer.file.sync_data());
+            if let Err(e) = written {
+                // Remove anything that was partly written, so that later entries follow the last
+                // complete one
+                let _ = writer.file.set_len(writer.len);
+                return Err(e.into());
+            }
+
+            writer.len += frame.len() as u64;
+            writer.next_seq += 1;
+            writer.head_hash = entry.hash;
+
+            Ok(())
+        })
+        .await
+        .map_err(|e| audit_log_error(format!("Failed to write entry: {e}")))?
+    }
+}
+
+#[async_trait]
+impl AgentSigner for AuditingAgentSigner {
+    async fn prepare(&self, cell_id: &CellId) -> Result<()> {
+        self.inner.prepare(cell_id).await
+    }
+
+    async fn prepare_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Result<()> {
+        self.inner.prepare_for_fn(cell_id, zome_name, fn_name).await
+    }
+
+    /// Refuses to sign, because only zome calls can be recorded in the audit log.
+    async fn sign(
+        &self,
+        _cell_id: &CellId,
+        _provenance: AgentPubKey,
+        _data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        Err(anyhow::anyhow!(
+            "Only zome calls can be signed with an auditing signer"
+        ))
+    }
+
+    async fn sign_zome_call_params(
+        &self,
+        signing_cell_id: &CellId,
+        params: &ZomeCallParams,
+        data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        let signature = self
+            .inner
+            .sign_zome_call_params(signing_cell_id, params, data_to_sign)
+            .await?;
+        self.record(params, &signature).await?;
+
+        Ok(signature)
+    }
+
+    fn get_provenance(&self, cell_id: &CellId) -> Option<AgentPubKey> {
+        self.inner.get_provenance(cell_id)
+    }
+
+    fn get_cap_secret(&self, cell_id: &CellId) -> Option<CapSecret> {
+        self.inner.get_cap_secret(cell_id)
+    }
+
+    fn get_provenance_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<AgentPubKey> {
+        self.inner
+            .get_provenance_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn get_cap_secret_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<CapSecret> {
+        self.inner
+            .get_cap_secret_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn get_credentials_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<(AgentPubKey, Option<CapSecret>)> {
+        self.inner
+            .get_credentials_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn update_app_info(&self, app_info: &AppInfo) {
+        self.inner.update_app_info(app_info)
+    }
+}
+
+impl From<AuditingAgentSigner> for DynAgentSigner {
+    fn from(signer: AuditingAgentSigner) -> Self {
+        Arc::new(signer)
+    }
+}
+
+/// Read all entries of an audit log, without verifying them.
+pub fn read_audit_log(path: impl AsRef<Path>) -> ConductorApiResult<Vec<AuditEntry>> {
+    let contents = read_audit_log_contents(&File::open(path)?)?;
+    if contents.truncated {
+        return Err(audit_log_error(format!(
+            "Entry {} is truncated",
+            contents.entries.len()
+        )));
+    }
+
+    Ok(contents.entries)
+}
+
+/// Verify that no entries of an audit log have been modified, removed or reordered, using the key
+/// that the log was written with.
+///
+/// Checks that the entries are numbered in order from 0, that the hash of each entry matches its
+/// contents, and that each entry refers to the hash of the entry before it. The first entry that
+/// fails a check is reported in the error.
+pub fn verify_audit_log(
+    path: impl AsRef<Path>,
+    key: &AuditLogKey,
+) -> ConductorApiResult<AuditLogSummary> {
+    verify_entries(&read_audit_log(path)?, key)
+}
+
+fn read_audit_log_contents(file: &File) -> ConductorApiResult<AuditLogContents> {
+    let mut reader = BufReader::new(file);
+    let mut contents = AuditLogContents {
+        entries: Vec::new(),
+        len: 0,
+        truncated: false,
+    };
+
+    loop {
+        let mut length = [0; 4];
+        match read_until_eof(&mut reader, &mut length)? {
+            0 => return Ok(contents),
+            4 => {}
+            _ => {
+                contents.truncated = true;
+                return Ok(contents);
+            }
+        }
+
+        let length = u32::from_be_bytes(length) as usize;
+        if length > MAX_ENTRY_BYTES {
+            return Err(audit_log_error(format!(
+                "Entry {} is too large, at {length} bytes",
+                contents.entries.len()
+            )));
+        }
+
+        let mut bytes = vec![0; length];
+        if read_until_eof(&mut reader, &mut bytes)? < length {
+            contents.truncated = true;
+            return Ok(contents);
+        }
+        let entry = ExternIO(bytes).decode().map_err(|_| {
+            audit_log_error(format!(
+                "Entry {} cannot be decoded",
+                contents.entries.len()
+            ))
+        })?;
+        contents.entries.push(entry);
+        contents.len += 4 + length as u64;
+    }
+}
+
+/// Read into `buf` until it is full or the end of the file is reached, and return the number of
+/// bytes read.
+fn read_until_eof(reader: &mut impl Read, buf: &mut [u8]) -> std::io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) => break,
+            Ok(n) => read += n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(read)
+}
+
+fn verify_entries(
+    entries: &[AuditEntry],
+    key: &AuditLogKey,
+) -> ConductorApiResult<AuditLogSummary> {
+    let mut summary = AuditLogSummary {
+        entries: 0,
+        head_hash: [0; HASH_BYTES],
+    };
+
+    for entry in entries {
+        if entry.seq != summary.entries {
+            return Err(audit_log_error(format!(
+                "Expected entry {} but found entry {}",
+                summary.entries, entry.seq
+            )));
+        }
+        if entry.prev_hash != summary.head_hash {
+            return Err(audit_log_error(format!(
+                "Entry {} does not follow the entry before it",
+                entry.seq
+            )));
+        }
+        if entry.compute_hash(key)? != entry.hash {
+            return Err(audit_log_error(format!(
+                "Entry {} has been modified",
+                entry.seq
+            )));
+        }
+
+        summary.entries += 1;
+        summary.head_hash = entry.hash;
+    }
+
+    Ok(summary)
+}
+
+fn audit_log_error(message: impl Into<String>) -> ConductorApiError {
+    ConductorApiError::AuditLogError(message.into())
+}
@@ -0,0 +1,244 @@
+use super::{client_signing::ClientAgentSigner, AgentSigner, DynAgentSigner};
+use crate::{util::functions_cover, AdminWebsocket, AuthorizeSigningCredentialsPayload};
+use anyhow::Result;
+use async_trait::async_trait;
+use holo_hash::AgentPubKey;
+use holochain_zome_types::{
+    capability::{CapSecret, GrantedFunctions},
+    cell::CellId,
+    dependencies::holochain_integrity_types::Signature,
+    prelude::{FunctionName, ZomeName},
+    zome_io::ZomeCallParams,
+};
+use std::{collections::HashMap, sync::Arc};
+use tokio::sync::Mutex;
+
+/// An [AgentSigner] which authorizes signing credentials for a cell the first time it needs to sign
+/// for it.
+///
+/// Credentials are authorized with [AdminWebsocket::authorize_signing_credentials] using the held
+/// admin connection, and added to a [ClientAgentSigner]. This includes clone cells that are created
+/// after the signer was created, so no credentials need to be set up before making zome calls.
+///
+/// Credentials are authorized again for a cell if the signer holds none which cover the function
+/// being called, for example because an existing [ClientAgentSigner] only has credentials for
+/// other functions. Each cell is only authorized once for the same functions, even if several zome
+/// calls to it are made concurrently, while calls to different cells are authorized in parallel.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// use std::net::Ipv4Addr;
+/// use holochain_client::{AdminWebsocket, AppWebsocket, AutoAuthorizingAgentSigner};
+///
+/// let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, 30_000)).await.unwrap();
+/// let issued = admin_ws.issue_app_auth_token("test-app".to_string().into()).await.unwrap();
+///
+/// let signer = AutoAuthorizingAgentSigner::new(admin_ws.clone(), None);
+/// let app_ws = AppWebsocket::connect((Ipv4Addr::LOCALHOST, 30_001), issued.token, signer.into()).await.unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct AutoAuthorizingAgentSigner {
+    admin_ws: AdminWebsocket,
+    signer: ClientAgentSigner,
+    functions: Option<GrantedFunctions>,
+    authorize_locks: Arc<parking_lot::Mutex<HashMap<CellId, Arc<Mutex<()>>>>>,
+}
+
+impl std::fmt::Debug for AutoAuthorizingAgentSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AutoAuthorizingAgentSigner")
+            .field("signer", &self.signer)
+            .field("functions", &self.functions)
+            .finish()
+    }
+}
+
+impl AutoAuthorizingAgentSigner {
+    /// Create a signer that authorizes credentials using the given admin connection.
+    ///
+    /// `functions` restricts the functions that credentials are granted for, or grants all
+    /// functions if `None`.
+    pub fn new(admin_ws: AdminWebsocket, functions: Option<GrantedFunctions>) -> Self {
+        Self::with_signer(admin_ws, functions, ClientAgentSigner::default())
+    }
+
+    /// Create a signer that adds credentials it authorizes to an existing [ClientAgentSigner].
+    ///
+    /// Cells that the signer already has credentials for are not authorized again, unless those
+    /// credentials don't cover the function being called.
+    pub fn with_signer(
+        admin_ws: AdminWebsocket,
+        functions: Option<GrantedFunctions>,
+        signer: ClientAgentSigner,
+    ) -> Self {
+        Self {
+            admin_ws,
+            signer,
+            functions,
+            authorize_locks: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// The signer which holds the authorized credentials.
+    pub fn client_signer(&self) -> &ClientAgentSigner {
+        &self.signer
+    }
+
+    fn granted_functions(&self) -> GrantedFunctions {
+        self.functions.clone().unwrap_or(GrantedFunctions::All)
+    }
+
+    /// Authorize credentials for the cell, unless `is_covered` finds that the signer already has
+    /// credentials for the call.
+    async fn authorize_unless_covered(
+        &self,
+        cell_id: &CellId,
+        is_covered: impl Fn() -> bool,
+    ) -> Result<()> {
+        if is_covered() {
+            return Ok(());
+        }
+
+        let lock = self
+            .authorize_locks
+            .lock()
+            .entry(cell_id.clone())
+            .or_default()
+            .clone();
+        let result = self.authorize_locked(&lock, cell_id, is_covered).await;
+
+        // Forget the lock once no other call is using it, so that locks are not kept for every
+        // cell that was ever called
+        let mut authorize_locks = self.authorize_locks.lock();
+        if Arc::strong_count(&lock) == 2 {
+            authorize_locks.remove(cell_id);
+        }
+
+        result
+    }
+
+    async fn authorize_locked(
+        &self,
+        lock: &Mutex<()>,
+        cell_id: &CellId,
+        is_covered: impl Fn() -> bool,
+    ) -> Result<()> {
+        let _guard = lock.lock().await;
+
+        // Another call may have authorized the cell while this one was waiting
+        if is_covered() {
+            return Ok(());
+        }
+
+        let credentials = self
+            .admin_ws
+            .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+                cell_id: cell_id.clone(),
+                functions: self.functions.clone(),
+            })
+            .await?;
+        self.signer
+            .add_scoped_credentials(cell_id.clone(), self.granted_functions(), credentials);
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AgentSigner for AutoAuthorizingAgentSigner {
+    async fn prepare(&self, cell_id: &CellId) -> Result<()> {
+        let functions = self.granted_functions();
+        self.authorize_unless_covered(cell_id, || {
+            self.signer.has_credentials_for(cell_id, &functions)
+        })
+        .await
+    }
+
+    async fn prepare_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Result<()> {
+        // Credentials authorized by this signer wouldn't cover the function, so there is nothing
+        // to authorize
+        if !functions_cover(&self.granted_functions(), zome_name, fn_name) {
+            return Ok(());
+        }
+
+        self.authorize_unless_covered(cell_id, || {
+            self.signer
+                .get_provenance_for_fn(cell_id, zome_name, fn_name)
+                .is_some()
+        })
+        .await
+    }
+
+    async fn sign(
+        &self,
+        cell_id: &CellId,
+        provenance: AgentPubKey,
+        data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        self.signer.sign(cell_id, provenance, data_to_sign).await
+    }
+
+    async fn sign_zome_call_params(
+        &self,
+        signing_cell_id: &CellId,
+        params: &ZomeCallParams,
+        data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        self.signer
+            .sign_zome_call_params(signing_cell_id, params, data_to_sign)
+            .await
+    }
+
+    fn get_provenance(&self, cell_id: &CellId) -> Option<AgentPubKey> {
+        self.signer.get_provenance(cell_id)
+    }
+
+    fn get_cap_secret(&self, cell_id: &CellId) -> Option<CapSecret> {
+        self.signer.get_cap_secret(cell_id)
+    }
+
+    fn get_provenance_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<AgentPubKey> {
+        self.signer
+            .get_provenance_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn get_cap_secret_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<CapSecret> {
+        self.signer
+            .get_cap_secret_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn get_credentials_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<(AgentPubKey, Option<CapSecret>)> {
+        self.signer
+            .get_credentials_for_fn(cell_id, zome_name, fn_name)
+    }
+}
+
+impl From<AutoAuthorizingAgentSigner> for DynAgentSigner {
+    fn from(signer: AutoAuthorizingAgentSigner) -> Self {
+        Arc::new(signer)
+    }
+}
@@ -1,12 +1,17 @@
 use super::{AgentSigner, DynAgentSigner};
+use crate::util::{functions_cover, functions_width};
 use async_trait::async_trait;
 use ed25519_dalek::Signer;
 use holo_hash::AgentPubKey;
+use holochain_nonce::FRESH_NONCE_EXPIRES_AFTER;
 use holochain_zome_types::{
-    capability::CapSecret, cell::CellId, dependencies::holochain_integrity_types::Signature,
+    capability::{CapSecret, GrantedFunctions},
+    cell::CellId,
+    dependencies::holochain_integrity_types::Signature,
+    prelude::{FunctionName, ZomeName},
 };
 use parking_lot::RwLock;
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, sync::Arc, time::Instant};
 
 pub struct SigningCredentials {
     pub signing_agent_key: AgentPubKey,
@@ -23,9 +28,44 @@ impl std::fmt::Debug for SigningCredentials {
     }
 }
 
+/// Signing credentials together with the functions that their capability was granted for.
+#[derive(Debug)]
+struct ScopedCredentials {
+    functions: GrantedFunctions,
+    credentials: SigningCredentials,
+    /// When the credentials were replaced by [ClientAgentSigner::swap_scoped_credentials].
+    retired_at: Option<Instant>,
+}
+
+impl ScopedCredentials {
+    fn new(functions: GrantedFunctions, credentials: SigningCredentials) -> Self {
+        Self {
+            functions,
+            credentials,
+            retired_at: None,
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.retired_at.is_none()
+    }
+
+    /// Retired credentials can still sign calls whose params were created before they were
+    /// retired, until those calls would have expired anyway.
+    fn can_sign(&self) -> bool {
+        self.retired_at
+            .is_none_or(|retired_at| retired_at.elapsed() < FRESH_NONCE_EXPIRES_AFTER)
+    }
+}
+
+/// An [AgentSigner] which holds signing credentials in memory.
+///
+/// A cell can have several sets of credentials, each scoped to the functions that its capability
+/// was granted for. When signing a call to a zome function, the narrowest credentials which cover
+/// that function are used, so that different parts of an app can run with least privilege.
 #[derive(Debug, Clone, Default)]
 pub struct ClientAgentSigner {
-    credentials: Arc<RwLock<HashMap<CellId, SigningCredentials>>>,
+    credentials: Arc<RwLock<HashMap<CellId, Vec<ScopedCredentials>>>>,
 }
 
 impl ClientAgentSigner {
@@ -35,8 +75,123 @@ impl ClientAgentSigner {
         }
     }
 
+    /// Add credentials which were granted access to all functions of the cell.
+    ///
+    /// This replaces any credentials for the cell that were also granted access to all functions.
     pub fn add_credentials(&self, cell_id: CellId, credentials: SigningCredentials) {
-        self.credentials.write().insert(cell_id, credentials);
+        self.add_scoped_credentials(cell_id, GrantedFunctions::All, credentials);
+    }
+
+    /// Add credentials which were granted access to the given functions of the cell.
+    ///
+    /// This replaces any credentials for the cell that were granted access to the same functions.
+    pub fn add_scoped_credentials(
+        &self,
+        cell_id: CellId,
+        functions: GrantedFunctions,
+        credentials: SigningCredentials,
+    ) {
+        let mut all_credentials = self.credentials.write();
+        let cell_credentials = all_credentials.entry(cell_id).or_default();
+        cell_credentials.retain(|c| c.functions != functions && c.can_sign());
+        cell_credentials.push(ScopedCredentials::new(functions, credentials));
+    }
+
+    /// Atomically replace the credentials for a cell that were granted access to the given
+    /// functions, and return the signing key of the credentials that were replaced.
+    ///
+    /// New zome calls are signed with the new credentials straight away. The replaced credentials
+    /// can still sign zome calls that were prepared with them before the swap, until those calls
+    /// would have expired, so that calls in flight are not interrupted.
+    pub fn swap_scoped_credentials(
+        &self,
+        cell_id: CellId,
+        functions: GrantedFunctions,
+        credentials: SigningCredentials,
+    ) -> Option<AgentPubKey> {
+        let mut all_credentials = self.credentials.write();
+        let cell_credentials = all_credentials.entry(cell_id).or_default();
+        cell_credentials.retain(|c| c.can_sign());
+
+        let mut replaced = None;
+        for c in cell_credentials
+            .iter_mut()
+            .filter(|c| c.is_active() && c.functions == functions)
+        {
+            c.retired_at = Some(Instant::now());
+            replaced = Some(c.credentials.signing_agent_key.clone());
+        }
+        cell_credentials.push(ScopedCredentials::new(functions, credentials));
+
+        replaced
+    }
+
+    /// Whether credentials have been added for the given cell.
+    pub fn has_credentials(&self, cell_id: &CellId) -> bool {
+        self.credentials
+            .read()
+            .get(cell_id)
+            .is_some_and(|credentials| credentials.iter().any(|c| c.is_active()))
+    }
+
+    /// Whether credentials have been added for the given cell which cover all of the given
+    /// functions.
+    pub fn has_credentials_for(&self, cell_id: &CellId, functions: &GrantedFunctions) -> bool {
+        match functions {
+            GrantedFunctions::All => {
+                self.credentials
+                    .read()
+                    .get(cell_id)
+                    .is_some_and(|credentials| {
+                        credentials
+                            .iter()
+                            .any(|c| c.is_active() && c.functions == GrantedFunctions::All)
+                    })
+            }
+            GrantedFunctions::Listed(functions) => functions.iter().all(|(zome_name, fn_name)| {
+                self.get_provenance_for_fn(cell_id, zome_name, fn_name)
+                    .is_some()
+            }),
+        }
+    }
+
+    /// The cells that this signer has credentials for, with the functions that each of their
+    /// credentials were granted access to.
+    pub(crate) fn scopes(&self) -> Vec<(CellId, GrantedFunctions)> {
+        self.credentials
+            .read()
+            .iter()
+            .flat_map(|(cell_id, credentials)| {
+                credentials
+                    .iter()
+                    .filter(|c| c.is_active())
+                    .map(|c| (cell_id.clone(), c.functions.clone()))
+            })
+            .collect()
+    }
+
+    /// Find the credentials for a cell and map them with `f`.
+    ///
+    /// If a function is given, the narrowest credentials which cover it are used. Otherwise the
+    /// widest credentials are used.
+    fn with_credentials<T>(
+        &self,
+        cell_id: &CellId,
+        function: Option<(&ZomeName, &FunctionName)>,
+        f: impl FnOnce(&SigningCredentials) -> T,
+    ) -> Option<T> {
+        let all_credentials = self.credentials.read();
+        let cell_credentials = all_credentials
+            .get(cell_id)?
+            .iter()
+            .filter(|c| c.is_active());
+        let scoped = match function {
+            Some((zome_name, fn_name)) => cell_credentials
+                .filter(|c| functions_cover(&c.functions, zome_name, fn_name))
+                .min_by_key(|c| functions_width(&c.functions)),
+            None => cell_credentials.max_by_key(|c| functions_width(&c.functions)),
+        }?;
+        Some(f(&scoped.credentials))
     }
 }
 
@@ -45,26 +200,59 @@ impl AgentSigner for ClientAgentSigner {
     async fn sign(
         &self,
         cell_id: &CellId,
-        _provenance: AgentPubKey,
+        provenance: AgentPubKey,
         data_to_sign: Arc<[u8]>,
     ) -> Result<Signature, anyhow::Error> {
         let credentials_lock = self.credentials.read();
         let credentials = credentials_lock
             .get(cell_id)
+            .and_then(|credentials| {
+                credentials
+                    .iter()
+                    .find(|c| c.credentials.signing_agent_key == provenance && c.can_sign())
+            })
             .ok_or_else(|| anyhow::anyhow!("No credentials found for cell: {:?}", cell_id))?;
-        let signature = credentials.keypair.try_sign(&data_to_sign)?;
+        let signature = credentials.credentials.keypair.try_sign(&data_to_sign)?;
         Ok(Signature(signature.to_bytes()))
     }
 
     fn get_provenance(&self, cell_id: &CellId) -> Option<AgentPubKey> {
-        self.credentials
-            .read()
-            .get(cell_id)
-            .map(|c| c.signing_agent_key.clone())
+        self.with_credentials(cell_id, None, |c| c.signing_agent_key.clone())
     }
 
     fn get_cap_secret(&self, cell_id: &CellId) -> Option<CapSecret> {
-        self.credentials.read().get(cell_id).map(|c| c.cap_secret)
+        self.with_credentials(cell_id, None, |c| c.cap_secret)
+    }
+
+    fn get_provenance_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<AgentPubKey> {
+        self.with_credentials(cell_id, Some((zome_name, fn_name)), |c| {
+            c.signing_agent_key.clone()
+        })
+    }
+
+    fn get_cap_secret_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<CapSecret> {
+        self.with_credentials(cell_id, Some((zome_name, fn_name)), |c| c.cap_secret)
+    }
+
+    fn get_credentials_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<(AgentPubKey, Option<CapSecret>)> {
+        self.with_credentials(cell_id, Some((zome_name, fn_name)), |c| {
+            (c.signing_agent_key.clone(), Some(c.cap_secret))
+        })
     }
 }
 
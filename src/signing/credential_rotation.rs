@@ -0,0 +1,188 @@
+use super::client_signing::ClientAgentSigner;
+use crate::{
+    util::AbortOnDropHandle, AdminWebsocket, AuthorizeSigningCredentialsPayload, ConductorApiError,
+    ConductorApiResult,
+};
+use holo_hash::AgentPubKey;
+use holochain_zome_types::{capability::GrantedFunctions, cell::CellId, prelude::Timestamp};
+use std::time::Duration;
+
+/// A capability grant whose signing key has been replaced by [CredentialRotator].
+///
+/// The grant still exists in the conductor, so anyone holding the old signing key and cap secret
+/// can still use it. Revoke it once it is no longer needed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupersededGrant {
+    pub cell_id: CellId,
+    /// The functions that the grant gives access to.
+    pub functions: GrantedFunctions,
+    /// The signing key that the grant was assigned to.
+    pub signing_agent_key: AgentPubKey,
+    /// The signing key of the grant that replaced it.
+    pub replaced_by: AgentPubKey,
+    pub superseded_at: Timestamp,
+}
+
+/// The outcome of [CredentialRotator::rotate_all].
+#[derive(Debug, Default)]
+pub struct RotationReport {
+    /// The grants that were superseded by new credentials.
+    pub superseded: Vec<SupersededGrant>,
+    /// The credentials that could not be rotated, with the reason. The signer keeps using the old
+    /// credentials for these.
+    pub failed: Vec<(CellId, GrantedFunctions, ConductorApiError)>,
+}
+
+impl RotationReport {
+    /// Whether all credentials were rotated.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Replaces the signing credentials held by a [ClientAgentSigner] with newly granted ones, without
+/// interrupting zome calls.
+///
+/// For each cell and set of functions that the signer has credentials for, new credentials are
+/// authorized with [AdminWebsocket::authorize_signing_credentials] and swapped in with
+/// [ClientAgentSigner::swap_scoped_credentials]. Zome calls that were already prepared with the
+/// old credentials are still signed with them.
+///
+/// New credentials always have a random signing key and cap secret. Credentials that were derived
+/// from a [SigningKeySeed](crate::SigningKeySeed) are replaced by random ones too, which cannot be
+/// derived from the seed again, so don't use a rotator for a signer whose credentials must be
+/// recoverable from a seed. Rotate those by authorizing credentials for the next index with
+/// [AdminWebsocket::authorize_signing_credentials_from_seed] instead.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// use std::net::Ipv4Addr;
+/// use std::time::Duration;
+/// use holochain_client::{AdminWebsocket, ClientAgentSigner, CredentialRotator};
+///
+/// let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, 30_000)).await.unwrap();
+/// let signer = ClientAgentSigner::default();
+/// // Add credentials to the signer
+///
+/// let rotator = CredentialRotator::new(admin_ws, signer);
+/// let _handle = rotator
+///     .schedule(Duration::from_secs(24 * 60 * 60), |report| {
+///         println!("Grants to revoke: {:?}", report.superseded);
+///         for (cell_id, _, e) in report.failed {
+///             eprintln!("Failed to rotate credentials for {cell_id:?}: {e}");
+///         }
+///     })
+///     .unwrap();
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CredentialRotator {
+    admin_ws: AdminWebsocket,
+    signer: ClientAgentSigner,
+}
+
+/// Stops scheduled rotation when dropped.
+pub struct RotationHandle {
+    _abort_handle: AbortOnDropHandle,
+}
+
+impl std::fmt::Debug for RotationHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RotationHandle").finish()
+    }
+}
+
+impl CredentialRotator {
+    pub fn new(admin_ws: AdminWebsocket, signer: ClientAgentSigner) -> Self {
+        Self { admin_ws, signer }
+    }
+
+    /// Grant new credentials for the given cell and functions, and swap them into the signer.
+    ///
+    /// Returns the grant that was superseded, if the signer had credentials for the same cell and
+    /// functions.
+    pub async fn rotate(
+        &self,
+        cell_id: &CellId,
+        functions: GrantedFunctions,
+    ) -> ConductorApiResult<Option<SupersededGrant>> {
+        let credentials = self
+            .admin_ws
+            .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+                cell_id: cell_id.clone(),
+                functions: Some(functions.clone()),
+            })
+            .await?;
+        let replaced_by = credentials.signing_agent_key.clone();
+
+        let superseded = self
+            .signer
+            .swap_scoped_credentials(cell_id.clone(), functions.clone(), credentials)
+            .map(|signing_agent_key| SupersededGrant {
+                cell_id: cell_id.clone(),
+                functions,
+                signing_agent_key,
+                replaced_by,
+                superseded_at: Timestamp::now(),
+            });
+
+        Ok(superseded)
+    }
+
+    /// Rotate all credentials held by the signer.
+    ///
+    /// Every set of credentials is attempted, even if some fail. The report includes both the
+    /// grants that were superseded and the credentials that failed to rotate, so that the grants
+    /// can be revoked even when some rotations fail.
+    pub async fn rotate_all(&self) -> RotationReport {
+        let mut report = RotationReport::default();
+
+        for (cell_id, functions) in self.signer.scopes() {
+            match self.rotate(&cell_id, functions.clone()).await {
+                Ok(grant) => report.superseded.extend(grant),
+                Err(e) => report.failed.push((cell_id, functions, e)),
+            }
+        }
+
+        report
+    }
+
+    /// Rotate all credentials held by the signer every `interval`, starting one interval from now.
+    ///
+    /// The report of each rotation is passed to `on_rotated`. Rotation stops when the returned
+    /// handle is dropped. Fails with [ConductorApiError::InvalidRotationInterval] if `interval` is
+    /// zero. An interval too long to be represented as a point in time never rotates.
+    pub fn schedule<F>(
+        self,
+        interval: Duration,
+        on_rotated: F,
+    ) -> ConductorApiResult<RotationHandle>
+    where
+        F: Fn(RotationReport) + Send + 'static,
+    {
+        if interval.is_zero() {
+            return Err(ConductorApiError::InvalidRotationInterval);
+        }
+
+        let start = tokio::time::Instant::now().checked_add(interval);
+        let task = tokio::spawn(async move {
+            let Some(start) = start else {
+                return;
+            };
+            let mut ticker = tokio::time::interval_at(start, interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                let report = self.rotate_all().await;
+                on_rotated(report);
+            }
+        });
+
+        Ok(RotationHandle {
+            _abort_handle: AbortOnDropHandle::new(task.abort_handle()),
+        })
+    }
+}
@@ -0,0 +1,298 @@
+use super::client_signing::{ClientAgentSigner, SigningCredentials};
+use crate::{ConductorApiError, ConductorApiResult};
+use argon2::Argon2;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use holo_hash::AgentPubKey;
+use holochain_zome_types::{capability::CapSecret, cell::CellId, zome_io::ExternIO};
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use zeroize::{Zeroize, Zeroizing};
+
+const STORE_VERSION: u8 = 1;
+const FILE_EXTENSION: &str = "cred";
+const SALT_BYTES: usize = 16;
+const NONCE_BYTES: usize = 24;
+
+/// A directory of [SigningCredentials], one file per [CellId], encrypted with a key derived from
+/// a passphrase.
+///
+/// Credentials are encrypted with XChaCha20-Poly1305 using a key derived from the passphrase with
+/// Argon2id. Each file has its own random salt and nonce, and is bound to its cell id so that files
+/// cannot be swapped between cells. The passphrase and derived keys are zeroized when dropped.
+///
+/// Use this to avoid authorizing new signing credentials every time your client starts.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use holochain_client::{
+///     AdminWebsocket, AuthorizeSigningCredentialsPayload, CellId, ClientAgentSigner,
+///     CredentialStore,
+/// };
+///
+/// async fn load_signer(admin_ws: &AdminWebsocket, cell_id: CellId) -> ClientAgentSigner {
+///     let store = CredentialStore::open("./credentials", "my passphrase").unwrap();
+///     let signer = ClientAgentSigner::default();
+///
+///     // Load credentials saved by a previous run
+///     store.load_all_into(&signer).unwrap();
+///
+///     if store.load(&cell_id).unwrap().is_none() {
+///         let credentials = admin_ws
+///             .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+///                 cell_id: cell_id.clone(),
+///                 functions: None,
+///             })
+///             .await
+///             .unwrap();
+///         store.save(&cell_id, &credentials).unwrap();
+///         signer.add_credentials(cell_id, credentials);
+///     }
+///
+///     signer
+/// }
+/// ```
+pub struct CredentialStore {
+    dir: PathBuf,
+    passphrase: Zeroizing<Vec<u8>>,
+}
+
+impl std::fmt::Debug for CredentialStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CredentialStore")
+            .field("dir", &self.dir)
+            .finish()
+    }
+}
+
+/// The on-disk format of a credentials file. Only the `ciphertext` is secret.
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedCredentials {
+    version: u8,
+    cell_id: CellId,
+    salt: [u8; SALT_BYTES],
+    nonce: [u8; NONCE_BYTES],
+    ciphertext: Vec<u8>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct StoredCredentials {
+    signing_agent_key: AgentPubKey,
+    keypair: [u8; ed25519_dalek::SECRET_KEY_LENGTH],
+    cap_secret: CapSecret,
+}
+
+/// Custom debug implementation which won't attempt to print the `cap_secret` or `keypair`
+impl std::fmt::Debug for StoredCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StoredCredentials")
+            .field("signing_agent_key", &self.signing_agent_key)
+            .finish()
+    }
+}
+
+impl Drop for StoredCredentials {
+    fn drop(&mut self) {
+        self.keypair.zeroize();
+    }
+}
+
+impl CredentialStore {
+    /// Open a credential store in the given directory, creating the directory if it does not
+    /// exist.
+    ///
+    /// The same passphrase must be used to load credentials as was used to save them.
+    pub fn open(
+        dir: impl Into<PathBuf>,
+        passphrase: impl Into<Vec<u8>>,
+    ) -> ConductorApiResult<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)?;
+
+        Ok(Self {
+            dir,
+            passphrase: Zeroizing::new(passphrase.into()),
+        })
+    }
+
+    /// Encrypt and save the credentials for a cell, replacing any credentials already saved for it.
+    pub fn save(
+        &self,
+        cell_id: &CellId,
+        credentials: &SigningCredentials,
+    ) -> ConductorApiResult<()> {
+        let stored = StoredCredentials {
+            signing_agent_key: credentials.signing_agent_key.clone(),
+            keypair: credentials.keypair.to_bytes(),
+            cap_secret: credentials.cap_secret,
+        };
+        let plaintext = Zeroizing::new(ExternIO::encode(&stored)?.0);
+
+        let mut salt = [0; SALT_BYTES];
+        let mut nonce = [0; NONCE_BYTES];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let key = self.derive_key(&salt)?;
+        let ciphertext = XChaCha20Poly1305::new(Key::from_slice(&*key))
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &associated_data(cell_id),
+                },
+            )
+            .map_err(|_| store_error("Failed to encrypt credentials"))?;
+
+        let encrypted = EncryptedCredentials {
+            version: STORE_VERSION,
+            cell_id: cell_id.clone(),
+            salt,
+            nonce,
+            ciphertext,
+        };
+        let path = self.path_for(cell_id);
+        write_private_file(&path, &ExternIO::encode(encrypted)?.0)?;
+
+        Ok(())
+    }
+
+    /// Load and decrypt the credentials for a cell, if any have been saved.
+    pub fn load(&self, cell_id: &CellId) -> ConductorApiResult<Option<SigningCredentials>> {
+        let path = self.path_for(cell_id);
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let (stored_cell_id, credentials) = self.load_file(&path)?;
+        if stored_cell_id != *cell_id {
+            return Err(store_error(format!(
+                "Credentials file {} is for a different cell",
+                path.display()
+            )));
+        }
+
+        Ok(Some(credentials))
+    }
+
+    /// Load all saved credentials into the given signer.
+    ///
+    /// Returns the ids of the cells that credentials were loaded for.
+    pub fn load_all_into(&self, signer: &ClientAgentSigner) -> ConductorApiResult<Vec<CellId>> {
+        let mut cell_ids = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(FILE_EXTENSION) {
+                continue;
+            }
+
+            let (cell_id, credentials) = self.load_file(&path)?;
+            signer.add_credentials(cell_id.clone(), credentials);
+            cell_ids.push(cell_id);
+        }
+
+        Ok(cell_ids)
+    }
+
+    /// Delete the saved credentials for a cell, if any.
+    pub fn remove(&self, cell_id: &CellId) -> ConductorApiResult<()> {
+        match std::fs::remove_file(self.path_for(cell_id)) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            result => Ok(result?),
+        }
+    }
+
+    fn load_file(&self, path: &Path) -> ConductorApiResult<(CellId, SigningCredentials)> {
+        let encrypted: EncryptedCredentials = ExternIO(std::fs::read(path)?).decode()?;
+        if encrypted.version != STORE_VERSION {
+            return Err(store_error(format!(
+                "Unsupported credentials file version {} in {}",
+                encrypted.version,
+                path.display()
+            )));
+        }
+
+        let key = self.derive_key(&encrypted.salt)?;
+        let mut plaintext = ExternIO(
+            XChaCha20Poly1305::new(Key::from_slice(&*key))
+                .decrypt(
+                    XNonce::from_slice(&encrypted.nonce),
+                    Payload {
+                        msg: &encrypted.ciphertext,
+                        aad: &associated_data(&encrypted.cell_id),
+                    },
+                )
+                .map_err(|_| {
+                    store_error(format!(
+                        "Failed to decrypt {}, the passphrase may be wrong",
+                        path.display()
+                    ))
+                })?,
+        );
+        let stored = plaintext.decode::<StoredCredentials>();
+        plaintext.0.zeroize();
+        let stored = stored?;
+
+        Ok((
+            encrypted.cell_id,
+            SigningCredentials {
+                signing_agent_key: stored.signing_agent_key.clone(),
+                keypair: ed25519_dalek::SigningKey::from_bytes(&stored.keypair),
+                cap_secret: stored.cap_secret,
+            },
+        ))
+    }
+
+    fn derive_key(&self, salt: &[u8]) -> ConductorApiResult<Zeroizing<[u8; 32]>> {
+        let mut key = Zeroizing::new([0; 32]);
+        Argon2::default()
+            .hash_password_into(&self.passphrase, salt, &mut *key)
+            .map_err(|e| store_error(format!("Failed to derive key: {e}")))?;
+
+        Ok(key)
+    }
+
+    fn path_for(&self, cell_id: &CellId) -> PathBuf {
+        self.dir.join(format!(
+            "{}-{}.{FILE_EXTENSION}",
+            cell_id.dna_hash(),
+            cell_id.agent_pubkey()
+        ))
+    }
+}
+
+/// Binds the ciphertext to the cell it was saved for.
+fn associated_data(cell_id: &CellId) -> Vec<u8> {
+    [
+        cell_id.dna_hash().get_raw_39(),
+        cell_id.agent_pubkey().get_raw_39(),
+    ]
+    .concat()
+}
+
+/// Write the file so that it is only readable by the current user, replacing it atomically.
+fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let tmp_path = path.with_extension("tmp");
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(&tmp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+    std::fs::rename(tmp_path, path)
+}
+
+fn store_error(message: impl Into<String>) -> ConductorApiError {
+    ConductorApiError::CredentialStoreError(message.into())
+}
@@ -1,18 +1,58 @@
-use crate::AgentSigner;
+use crate::{AgentSigner, ConductorApiError, ConductorApiResult, DynAgentSigner};
 use anyhow::Result;
 use async_trait::async_trait;
 use holo_hash::AgentPubKey;
+use holochain_conductor_api::{
+    conductor::{ConductorConfig, KeystoreConfig},
+    AppInfo, CellInfo,
+};
 use holochain_zome_types::{
-    capability::CapSecret, cell::CellId, dependencies::holochain_integrity_types::Signature,
+    capability::CapSecret,
+    cell::CellId,
+    dependencies::holochain_integrity_types::Signature,
+    prelude::{FunctionName, ZomeName},
+};
+use lair_keystore_api::{
+    config::LairServerConfigInner,
+    dependencies::{hc_seed_bundle::dependencies::sodoken, url::Url},
+    ipc_keystore::ipc_keystore_connect,
+    LairClient,
 };
-use lair_keystore_api::LairClient;
 use parking_lot::RwLock;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// The name of the file in the Lair root directory that holds the Lair server config.
+const LAIR_CONFIG_FILENAME: &str = "lair-keystore-config.yaml";
+
+/// Signing credentials whose private key is held in Lair.
+///
+/// Created with
+/// [AdminWebsocket::authorize_lair_signing_credentials](crate::AdminWebsocket::authorize_lair_signing_credentials).
+#[derive(Clone)]
+pub struct LairSigningCredentials {
+    pub signing_agent_key: AgentPubKey,
+    pub cap_secret: CapSecret,
+}
+
+/// Custom debug implementation which won't attempt to print the `cap_secret`
+impl std::fmt::Debug for LairSigningCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LairSigningCredentials")
+            .field("signing_agent_key", &self.signing_agent_key)
+            .finish()
+    }
+}
+
+struct LairCredentials {
+    provenance: AgentPubKey,
+    cap_secret: Option<CapSecret>,
+}
 
 pub struct LairAgentSigner {
     lair_client: Arc<LairClient>,
-    credentials: Arc<RwLock<HashMap<CellId, AgentPubKey>>>,
+    credentials: Arc<RwLock<HashMap<CellId, LairCredentials>>>,
 }
 
 impl LairAgentSigner {
@@ -23,10 +63,136 @@ impl LairAgentSigner {
         }
     }
 
+    /// Connect to a Lair keystore from its connection URL and passphrase.
+    ///
+    /// The connection URL can be found in the `lair-keystore-config.yaml` file in the Lair root
+    /// directory, or in the `keystore` section of the conductor config if the conductor connects
+    /// to a separate Lair process.
+    pub async fn connect(
+        connection_url: &str,
+        passphrase: impl Into<Vec<u8>>,
+    ) -> ConductorApiResult<Self> {
+        let connection_url = Url::parse(connection_url).map_err(|e| {
+            ConductorApiError::KeystoreError(format!("Invalid Lair connection URL: {e}"))
+        })?;
+        let passphrase = Arc::new(Mutex::new(sodoken::LockedArray::from(passphrase.into())));
+        let lair_client = ipc_keystore_connect(connection_url, passphrase)
+            .await
+            .map_err(|e| {
+                ConductorApiError::KeystoreError(format!("Failed to connect to Lair: {e}"))
+            })?;
+
+        Ok(Self::new(Arc::new(lair_client)))
+    }
+
+    /// Connect to the Lair keystore used by a conductor, using the conductor's config file.
+    ///
+    /// This works whether the conductor connects to a separate Lair process, or runs Lair in
+    /// process. In the second case the conductor must be running for the connection to succeed.
+    pub async fn connect_from_conductor_config(
+        config_path: impl AsRef<Path>,
+        passphrase: impl Into<Vec<u8>>,
+    ) -> ConductorApiResult<Self> {
+        // Loading the config reads the file synchronously, so it shouldn't block the runtime
+        let config_path = config_path.as_ref().to_path_buf();
+        let config = tokio::task::spawn_blocking(move || ConductorConfig::load_yaml(&config_path))
+            .await
+            .map_err(|e| {
+                ConductorApiError::KeystoreError(format!("Failed to load conductor config: {e}"))
+            })?
+            .map_err(|e| {
+                ConductorApiError::KeystoreError(format!("Failed to load conductor config: {e}"))
+            })?;
+
+        let connection_url = match config.keystore {
+            KeystoreConfig::LairServer { connection_url } => connection_url.to_string(),
+            KeystoreConfig::LairServerInProc { lair_root } => {
+                let lair_root = match (lair_root, config.data_root_path) {
+                    (Some(lair_root), _) => lair_root.to_path_buf(),
+                    (None, Some(data_root_path)) => data_root_path.join("ks"),
+                    (None, None) => {
+                        return Err(ConductorApiError::KeystoreError(
+                            "Conductor config does not set a Lair root or data root path"
+                                .to_string(),
+                        ))
+                    }
+                };
+                let lair_config = tokio::fs::read(lair_root.join(LAIR_CONFIG_FILENAME))
+                    .await
+                    .map_err(|e| {
+                        ConductorApiError::KeystoreError(format!(
+                            "Failed to read Lair config from {}: {e}",
+                            lair_root.display()
+                        ))
+                    })?;
+                LairServerConfigInner::from_bytes(&lair_config)
+                    .map_err(|e| {
+                        ConductorApiError::KeystoreError(format!("Invalid Lair config: {e}"))
+                    })?
+                    .connection_url
+                    .to_string()
+            }
+            _ => {
+                return Err(ConductorApiError::KeystoreError(
+                    "Conductor is not configured to use Lair".to_string(),
+                ))
+            }
+        };
+
+        Self::connect(&connection_url, passphrase).await
+    }
+
     /// Add credentials for a cell to the signer.
     /// The provenance should be the `agent_pub_key` that the cell is running as.
     pub fn add_credentials(&mut self, cell_id: CellId, provenance: AgentPubKey) {
-        self.credentials.write().insert(cell_id, provenance);
+        self.credentials.write().insert(
+            cell_id,
+            LairCredentials {
+                provenance,
+                cap_secret: None,
+            },
+        );
+    }
+
+    /// Add credentials for a cell whose signing key was created in Lair and granted a capability,
+    /// so that zome calls are signed by that key and include the cap secret.
+    pub fn add_signing_credentials(
+        &mut self,
+        cell_id: CellId,
+        credentials: LairSigningCredentials,
+    ) {
+        self.credentials.write().insert(
+            cell_id,
+            LairCredentials {
+                provenance: credentials.signing_agent_key,
+                cap_secret: Some(credentials.cap_secret),
+            },
+        );
+    }
+
+    /// The Lair client that is used to sign.
+    pub fn lair_client(&self) -> Arc<LairClient> {
+        self.lair_client.clone()
+    }
+
+    /// Add credentials for every provisioned and cloned cell of an app, using the app's
+    /// `agent_pub_key` as the provenance.
+    pub fn add_credentials_for_app(&mut self, app_info: &AppInfo) {
+        let mut credentials = self.credentials.write();
+        for cell in app_info.cell_info.values().flatten() {
+            let cell_id = match cell {
+                CellInfo::Provisioned(provisioned_cell) => &provisioned_cell.cell_id,
+                CellInfo::Cloned(cloned_cell) => &cloned_cell.cell_id,
+                _ => continue,
+            };
+            credentials.insert(
+                cell_id.clone(),
+                LairCredentials {
+                    provenance: app_info.agent_pub_key.clone(),
+                    cap_secret: None,
+                },
+            );
+        }
     }
 }
 
@@ -49,12 +215,35 @@ impl AgentSigner for LairAgentSigner {
     }
 
     fn get_provenance(&self, cell_id: &CellId) -> Option<AgentPubKey> {
-        self.credentials.read().get(cell_id).cloned()
+        self.credentials
+            .read()
+            .get(cell_id)
+            .map(|c| c.provenance.clone())
+    }
+
+    /// Only set for credentials added with [LairAgentSigner::add_signing_credentials]. When signing
+    /// as the agent that a cell is running as, no cap secret is needed because the agent is the
+    /// author of the cell.
+    fn get_cap_secret(&self, cell_id: &CellId) -> Option<CapSecret> {
+        self.credentials.read().get(cell_id)?.cap_secret
     }
 
-    /// Not used with Lair signing. If you have access to Lair then you don't need to prove you
-    // are supposed to have access to a specific key pair.
-    fn get_cap_secret(&self, _cell_id: &CellId) -> Option<CapSecret> {
-        None
+    fn get_credentials_for_fn(
+        &self,
+        cell_id: &CellId,
+        _zome_name: &ZomeName,
+        _fn_name: &FunctionName,
+    ) -> Option<(AgentPubKey, Option<CapSecret>)> {
+        self.credentials
+            .read()
+            .get(cell_id)
+            .map(|c| (c.provenance.clone(), c.cap_secret))
+    }
+}
+
+/// Convert the LairAgentSigner into an `Arc<dyn AgentSigner + Send + Sync>`
+impl From<LairAgentSigner> for DynAgentSigner {
+    fn from(las: LairAgentSigner) -> Self {
+        Arc::new(las)
     }
 }
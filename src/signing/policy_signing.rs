@@ -0,0 +1,250 @@
+use super::{AgentSigner, DynAgentSigner};
+use anyhow::Result;
+use async_trait::async_trait;
+use holo_hash::AgentPubKey;
+use holochain_conductor_api::AppInfo;
+use holochain_zome_types::{
+    capability::CapSecret,
+    cell::CellId,
+    dependencies::holochain_integrity_types::Signature,
+    prelude::{FunctionName, Timestamp, ZomeName},
+    zome_io::ZomeCallParams,
+};
+use parking_lot::Mutex;
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+/// Limits the number of zome calls that may be signed in a period of time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    /// The maximum number of zome calls that may be signed in any `period`.
+    pub max_signatures: usize,
+    pub period: Duration,
+}
+
+/// Rules about which zome calls a [PolicyAgentSigner] will sign.
+///
+/// The default policy allows every zome call.
+#[derive(Debug, Clone, Default)]
+pub struct SigningPolicy {
+    /// If set, only calls to these functions are signed.
+    pub allowed_functions: Option<HashSet<(ZomeName, FunctionName)>>,
+    /// Calls to these functions are never signed, even if they are also allowed.
+    pub denied_functions: HashSet<(ZomeName, FunctionName)>,
+    /// The largest payload, in bytes, that will be signed.
+    pub max_payload_bytes: Option<usize>,
+    /// The furthest in the future that a zome call may expire.
+    pub max_expires_in: Option<Duration>,
+    /// The rate at which zome calls are signed, across all cells.
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// The reason that a [PolicyAgentSigner] refused to sign a zome call.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PolicyViolation {
+    #[error("Function {0}::{1} is not allowed by the signing policy")]
+    FunctionNotAllowed(ZomeName, FunctionName),
+    #[error("Function {0}::{1} is denied by the signing policy")]
+    FunctionDenied(ZomeName, FunctionName),
+    #[error("Payload of {size} bytes is larger than the maximum of {max} bytes")]
+    PayloadTooLarge { size: usize, max: usize },
+    #[error("Zome call expires too far in the future")]
+    ExpiryTooLate,
+    #[error("Signing rate limit exceeded")]
+    RateLimited,
+    #[error("Only zome calls can be signed with a signing policy")]
+    MissingZomeCall,
+}
+
+/// An [AgentSigner] which checks each zome call against a [SigningPolicy] before delegating to an
+/// inner signer.
+///
+/// Zome calls which do not satisfy the policy are refused with a [PolicyViolation], which is
+/// returned from [AppWebsocket::call_zome](crate::AppWebsocket::call_zome) as
+/// [ConductorApiError::SigningPolicyViolation](crate::ConductorApiError::SigningPolicyViolation).
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use holochain_client::{ClientAgentSigner, PolicyAgentSigner, RateLimit, SigningPolicy};
+///
+/// let signer = PolicyAgentSigner::new(
+///     ClientAgentSigner::default().into(),
+///     SigningPolicy {
+///         allowed_functions: Some([("foo".into(), "foo".into())].into()),
+///         max_payload_bytes: Some(1024),
+///         rate_limit: Some(RateLimit {
+///             max_signatures: 10,
+///             period: Duration::from_secs(1),
+///         }),
+///         ..Default::default()
+///     },
+/// );
+/// ```
+#[derive(Clone)]
+pub struct PolicyAgentSigner {
+    inner: DynAgentSigner,
+    policy: SigningPolicy,
+    signed_at: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+impl std::fmt::Debug for PolicyAgentSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PolicyAgentSigner")
+            .field("policy", &self.policy)
+            .finish()
+    }
+}
+
+impl PolicyAgentSigner {
+    pub fn new(inner: DynAgentSigner, policy: SigningPolicy) -> Self {
+        Self {
+            inner,
+            policy,
+            signed_at: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// The policy that zome calls are checked against.
+    pub fn policy(&self) -> &SigningPolicy {
+        &self.policy
+    }
+
+    fn check(&self, params: &ZomeCallParams) -> Result<(), PolicyViolation> {
+        let function = (params.zome_name.clone(), params.fn_name.clone());
+        if self.policy.denied_functions.contains(&function) {
+            return Err(PolicyViolation::FunctionDenied(function.0, function.1));
+        }
+        if let Some(allowed_functions) = &self.policy.allowed_functions {
+            if !allowed_functions.contains(&function) {
+                return Err(PolicyViolation::FunctionNotAllowed(function.0, function.1));
+            }
+        }
+
+        if let Some(max) = self.policy.max_payload_bytes {
+            let size = params.payload.0.len();
+            if size > max {
+                return Err(PolicyViolation::PayloadTooLarge { size, max });
+            }
+        }
+
+        if let Some(max_expires_in) = self.policy.max_expires_in {
+            // The expiry comes from the caller, so it can be anywhere in the timestamp range
+            let expires_in = params
+                .expires_at
+                .as_micros()
+                .saturating_sub(Timestamp::now().as_micros());
+            if expires_in > i64::try_from(max_expires_in.as_micros()).unwrap_or(i64::MAX) {
+                return Err(PolicyViolation::ExpiryTooLate);
+            }
+        }
+
+        // Checked last so that refused calls do not count towards the limit
+        if let Some(rate_limit) = self.policy.rate_limit {
+            let now = Instant::now();
+            let mut signed_at = self.signed_at.lock();
+            while signed_at
+                .front()
+                .is_some_and(|t| now.duration_since(*t) >= rate_limit.period)
+            {
+                signed_at.pop_front();
+            }
+            if signed_at.len() >= rate_limit.max_signatures {
+                return Err(PolicyViolation::RateLimited);
+            }
+            signed_at.push_back(now);
+        }
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl AgentSigner for PolicyAgentSigner {
+    async fn prepare(&self, cell_id: &CellId) -> Result<()> {
+        self.inner.prepare(cell_id).await
+    }
+
+    async fn prepare_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Result<()> {
+        self.inner.prepare_for_fn(cell_id, zome_name, fn_name).await
+    }
+
+    /// Refuses to sign, because the policy cannot be checked without the zome call.
+    async fn sign(
+        &self,
+        _cell_id: &CellId,
+        _provenance: AgentPubKey,
+        _data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        Err(PolicyViolation::MissingZomeCall.into())
+    }
+
+    async fn sign_zome_call_params(
+        &self,
+        signing_cell_id: &CellId,
+        params: &ZomeCallParams,
+        data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        self.check(params)?;
+        self.inner
+            .sign_zome_call_params(signing_cell_id, params, data_to_sign)
+            .await
+    }
+
+    fn get_provenance(&self, cell_id: &CellId) -> Option<AgentPubKey> {
+        self.inner.get_provenance(cell_id)
+    }
+
+    fn get_cap_secret(&self, cell_id: &CellId) -> Option<CapSecret> {
+        self.inner.get_cap_secret(cell_id)
+    }
+
+    fn get_provenance_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<AgentPubKey> {
+        self.inner
+            .get_provenance_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn get_cap_secret_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<CapSecret> {
+        self.inner
+            .get_cap_secret_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn get_credentials_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<(AgentPubKey, Option<CapSecret>)> {
+        self.inner
+            .get_credentials_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn update_app_info(&self, app_info: &AppInfo) {
+        self.inner.update_app_info(app_info)
+    }
+}
+
+impl From<PolicyAgentSigner> for DynAgentSigner {
+    fn from(signer: PolicyAgentSigner) -> Self {
+        Arc::new(signer)
+    }
+}
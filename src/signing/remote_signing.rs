@@ -0,0 +1,664 @@
+//! A signer which holds its keys in a separate process.
+//!
+//! A [RemoteSignerDaemon] hosts any [AgentSigner], such as a [ClientAgentSigner](crate::ClientAgentSigner),
+//! and listens on a Unix socket or a loopback TCP port. A [RemoteAgentSigner] connects to it and
+//! forwards signing requests, so that private keys never enter the client process.
+//!
+//! Both sides share a [RemoteSignerKey]. When a connection is opened, each side sends a random
+//! challenge and proves that it knows the key by returning a keyed BLAKE2b hash of both
+//! challenges. Requests are only accepted once both proofs have been checked, and either side
+//! gives up on a connection which doesn't complete the handshake within a few seconds.
+//!
+//! After the handshake, requests and responses are sent as MessagePack frames, each prefixed with
+//! its length as a big-endian `u32`.
+
+use super::{AgentSigner, DynAgentSigner};
+use crate::{ConductorApiError, ConductorApiResult};
+use anyhow::Result;
+use async_trait::async_trait;
+use holo_hash::AgentPubKey;
+use holochain_zome_types::{
+    capability::CapSecret,
+    cell::CellId,
+    dependencies::holochain_integrity_types::Signature,
+    prelude::{FunctionName, ZomeName},
+    zome_io::{ExternIO, ZomeCallParams},
+};
+use parking_lot::RwLock;
+use rand::{rngs::OsRng, RngCore};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::Mutex,
+};
+
+const CHALLENGE_BYTES: usize = 32;
+const PROOF_BYTES: usize = 32;
+const MAX_FRAME_BYTES: usize = 16 * 1024 * 1024;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(100);
+const DAEMON_PERSONAL: &[u8] = b"hc_signer_daemon";
+const CLIENT_PERSONAL: &[u8] = b"hc_signer_client";
+
+/// The shared secret that a [RemoteAgentSigner] and a [RemoteSignerDaemon] use to authenticate
+/// each other.
+///
+/// Anyone who knows the key can sign with the daemon, so it must be kept secret.
+#[derive(Clone)]
+pub struct RemoteSignerKey([u8; 32]);
+
+/// Custom debug implementation which won't attempt to print the key
+impl std::fmt::Debug for RemoteSignerKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteSignerKey").finish()
+    }
+}
+
+impl RemoteSignerKey {
+    /// Use the given bytes as the key.
+    pub fn from_bytes(key: [u8; 32]) -> Self {
+        Self(key)
+    }
+
+    /// Generate a new random key.
+    pub fn generate() -> Self {
+        let mut key = [0; 32];
+        OsRng.fill_bytes(&mut key);
+        Self(key)
+    }
+
+    /// The bytes of the key, so that it can be shared with the other side.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    fn proof(
+        &self,
+        personal: &[u8],
+        client_challenge: &[u8; CHALLENGE_BYTES],
+        daemon_challenge: &[u8; CHALLENGE_BYTES],
+    ) -> [u8; PROOF_BYTES] {
+        let hash = blake2b_simd::Params::new()
+            .hash_length(PROOF_BYTES)
+            .key(&self.0)
+            .personal(personal)
+            .to_state()
+            .update(client_challenge)
+            .update(daemon_challenge)
+            .finalize();
+        let mut proof = [0; PROOF_BYTES];
+        proof.copy_from_slice(hash.as_bytes());
+        proof
+    }
+}
+
+/// Where a [RemoteSignerDaemon] is listening.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteSignerEndpoint {
+    /// A TCP port, which must be on a loopback address.
+    Tcp(SocketAddr),
+    /// A Unix domain socket.
+    #[cfg(unix)]
+    Unix(std::path::PathBuf),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum RemoteSignerRequest {
+    Prepare(CellId),
+    PrepareForFn {
+        cell_id: CellId,
+        zome_name: ZomeName,
+        fn_name: FunctionName,
+    },
+    Sign {
+        cell_id: CellId,
+        provenance: AgentPubKey,
+        data_to_sign: Vec<u8>,
+    },
+    SignZomeCall {
+        signing_cell_id: CellId,
+        params: Box<ZomeCallParams>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum RemoteSignerResponse {
+    Prepared {
+        provenance: Option<AgentPubKey>,
+        cap_secret: Option<CapSecret>,
+    },
+    Signature(Signature),
+    Error(String),
+}
+
+trait Connection: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Connection for T {}
+
+/// An [AgentSigner] which forwards signing requests to a [RemoteSignerDaemon].
+///
+/// The provenance and cap secret for a cell, or for the zome function being called, are fetched
+/// from the daemon each time a zome call is prepared, so that the daemon can use credentials
+/// scoped to the function. A single connection is used for all requests, and is opened again if
+/// it is lost.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// use std::net::Ipv4Addr;
+/// use holochain_client::{RemoteAgentSigner, RemoteSignerEndpoint, RemoteSignerKey};
+///
+/// let key = RemoteSignerKey::from_bytes([0; 32]);
+/// let endpoint = RemoteSignerEndpoint::Tcp((Ipv4Addr::LOCALHOST, 30_002).into());
+/// let signer = RemoteAgentSigner::connect(endpoint, key).await.unwrap();
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RemoteAgentSigner {
+    endpoint: RemoteSignerEndpoint,
+    key: RemoteSignerKey,
+    connection: Arc<Mutex<Option<Box<dyn Connection>>>>,
+    prepared: Arc<RwLock<HashMap<CellId, PreparedCredentials>>>,
+    prepared_for_fn: Arc<RwLock<HashMap<FunctionKey, PreparedCredentials>>>,
+}
+
+type PreparedCredentials = (Option<AgentPubKey>, Option<CapSecret>);
+
+type FunctionKey = (CellId, ZomeName, FunctionName);
+
+impl std::fmt::Debug for RemoteAgentSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteAgentSigner")
+            .field("endpoint", &self.endpoint)
+            .finish()
+    }
+}
+
+impl RemoteAgentSigner {
+    /// Connect to a daemon and authenticate with the given key.
+    pub async fn connect(
+        endpoint: RemoteSignerEndpoint,
+        key: RemoteSignerKey,
+    ) -> ConductorApiResult<Self> {
+        let connection = open_connection(&endpoint, &key).await?;
+
+        Ok(Self {
+            endpoint,
+            key,
+            connection: Arc::new(Mutex::new(Some(connection))),
+            prepared: Arc::new(RwLock::new(HashMap::new())),
+            prepared_for_fn: Arc::new(RwLock::new(HashMap::new())),
+        })
+    }
+
+    async fn request(&self, request: RemoteSignerRequest) -> Result<RemoteSignerResponse> {
+        let request = ExternIO::encode(request)?;
+        let mut connection = self.connection.lock().await;
+
+        // Retry once on a new connection, in case the daemon was restarted
+        let mut retried = false;
+        loop {
+            // The stream is only put back once a full response has been read, so that if this
+            // future is dropped mid-exchange, the next request doesn't read a stale response
+            let mut stream = match connection.take() {
+                Some(stream) => stream,
+                None => open_connection(&self.endpoint, &self.key).await?,
+            };
+            match exchange(&mut stream, &request).await {
+                Ok(response) => {
+                    *connection = Some(stream);
+                    return match response.decode::<RemoteSignerResponse>()? {
+                        RemoteSignerResponse::Error(e) => Err(anyhow::anyhow!(e)),
+                        response => Ok(response),
+                    };
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::InvalidInput => {
+                    // The request was refused before it was sent, so the connection can be used
+                    // again, but retrying the request cannot succeed
+                    *connection = Some(stream);
+                    return Err(e.into());
+                }
+                Err(e) => {
+                    if retried {
+                        return Err(e.into());
+                    }
+                    retried = true;
+                }
+            }
+        }
+    }
+}
+
+async fn exchange(
+    stream: &mut (impl AsyncRead + AsyncWrite + Unpin),
+    request: &ExternIO,
+) -> std::io::Result<ExternIO> {
+    write_frame(stream, &request.0).await?;
+    Ok(ExternIO(read_frame(stream).await?))
+}
+
+#[async_trait]
+impl AgentSigner for RemoteAgentSigner {
+    async fn prepare(&self, cell_id: &CellId) -> Result<()> {
+        match self
+            .request(RemoteSignerRequest::Prepare(cell_id.clone()))
+            .await?
+        {
+            RemoteSignerResponse::Prepared {
+                provenance,
+                cap_secret,
+            } => {
+                self.prepared
+                    .write()
+                    .insert(cell_id.clone(), (provenance, cap_secret));
+                Ok(())
+            }
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    async fn prepare_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Result<()> {
+        match self
+            .request(RemoteSignerRequest::PrepareForFn {
+                cell_id: cell_id.clone(),
+                zome_name: zome_name.clone(),
+                fn_name: fn_name.clone(),
+            })
+            .await?
+        {
+            RemoteSignerResponse::Prepared {
+                provenance,
+                cap_secret,
+            } => {
+                self.prepared_for_fn.write().insert(
+                    (cell_id.clone(), zome_name.clone(), fn_name.clone()),
+                    (provenance, cap_secret),
+                );
+                Ok(())
+            }
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    async fn sign(
+        &self,
+        cell_id: &CellId,
+        provenance: AgentPubKey,
+        data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        match self
+            .request(RemoteSignerRequest::Sign {
+                cell_id: cell_id.clone(),
+                provenance,
+                data_to_sign: data_to_sign.to_vec(),
+            })
+            .await?
+        {
+            RemoteSignerResponse::Signature(signature) => Ok(signature),
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    /// Sends the zome call to the daemon, which hashes and signs it so that it knows what it is
+    /// signing.
+    async fn sign_zome_call_params(
+        &self,
+        signing_cell_id: &CellId,
+        params: &ZomeCallParams,
+        _data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        match self
+            .request(RemoteSignerRequest::SignZomeCall {
+                signing_cell_id: signing_cell_id.clone(),
+                params: Box::new(params.clone()),
+            })
+            .await?
+        {
+            RemoteSignerResponse::Signature(signature) => Ok(signature),
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    fn get_provenance(&self, cell_id: &CellId) -> Option<AgentPubKey> {
+        self.prepared.read().get(cell_id)?.0.clone()
+    }
+
+    fn get_cap_secret(&self, cell_id: &CellId) -> Option<CapSecret> {
+        self.prepared.read().get(cell_id)?.1
+    }
+
+    fn get_provenance_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<AgentPubKey> {
+        match self.prepared_for_fn.read().get(&(
+            cell_id.clone(),
+            zome_name.clone(),
+            fn_name.clone(),
+        )) {
+            Some((provenance, _)) => provenance.clone(),
+            None => self.get_provenance(cell_id),
+        }
+    }
+
+    fn get_cap_secret_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<CapSecret> {
+        match self.prepared_for_fn.read().get(&(
+            cell_id.clone(),
+            zome_name.clone(),
+            fn_name.clone(),
+        )) {
+            Some((_, cap_secret)) => *cap_secret,
+            None => self.get_cap_secret(cell_id),
+        }
+    }
+
+    fn get_credentials_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<(AgentPubKey, Option<CapSecret>)> {
+        let (provenance, cap_secret) = match self.prepared_for_fn.read().get(&(
+            cell_id.clone(),
+            zome_name.clone(),
+            fn_name.clone(),
+        )) {
+            Some(prepared) => prepared.clone(),
+            None => self.prepared.read().get(cell_id)?.clone(),
+        };
+        Some((provenance?, cap_secret))
+    }
+}
+
+impl From<RemoteAgentSigner> for DynAgentSigner {
+    fn from(signer: RemoteAgentSigner) -> Self {
+        Arc::new(signer)
+    }
+}
+
+fn unexpected_response(response: RemoteSignerResponse) -> anyhow::Error {
+    anyhow::anyhow!("Unexpected response from remote signer: {:?}", response)
+}
+
+/// A daemon which signs on behalf of [RemoteAgentSigner]s, using a signer that it hosts.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # #[tokio::main]
+/// # async fn main() {
+/// use holochain_client::{ClientAgentSigner, RemoteSignerDaemon, RemoteSignerKey};
+///
+/// let signer = ClientAgentSigner::default();
+/// // Add credentials to the signer
+///
+/// let listener = tokio::net::TcpListener::bind("127.0.0.1:30002").await.unwrap();
+/// let daemon = RemoteSignerDaemon::new(signer.into(), RemoteSignerKey::from_bytes([0; 32]));
+/// daemon.serve_tcp(listener).await;
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct RemoteSignerDaemon {
+    signer: DynAgentSigner,
+    key: RemoteSignerKey,
+}
+
+impl std::fmt::Debug for RemoteSignerDaemon {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RemoteSignerDaemon").finish()
+    }
+}
+
+impl RemoteSignerDaemon {
+    pub fn new(signer: DynAgentSigner, key: RemoteSignerKey) -> Self {
+        Self { signer, key }
+    }
+
+    /// Accept connections on a TCP listener until this future is dropped.
+    ///
+    /// Connections which are not from a loopback address are closed immediately. Errors accepting
+    /// a connection, such as running out of file descriptors, are retried after a short delay.
+    pub async fn serve_tcp(self, listener: TcpListener) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    if !peer.ip().is_loopback() {
+                        continue;
+                    }
+                    tokio::spawn(self.clone().serve_connection(stream));
+                }
+                Err(e) => accept_failed(e).await,
+            }
+        }
+    }
+
+    /// Accept connections on a Unix socket listener until this future is dropped.
+    ///
+    /// Errors accepting a connection, such as running out of file descriptors, are retried after a
+    /// short delay.
+    #[cfg(unix)]
+    pub async fn serve_unix(self, listener: tokio::net::UnixListener) {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(self.clone().serve_connection(stream));
+                }
+                Err(e) => accept_failed(e).await,
+            }
+        }
+    }
+
+    async fn serve_connection(self, mut stream: impl Connection) -> std::io::Result<()> {
+        tokio::time::timeout(HANDSHAKE_TIMEOUT, self.accept_handshake(&mut stream))
+            .await
+            .map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "Remote signer client did not complete the handshake in time",
+                )
+            })??;
+
+        loop {
+            let request = match read_frame(&mut stream).await {
+                Ok(request) => request,
+                Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(()),
+                Err(e) => return Err(e),
+            };
+            let response = match ExternIO(request).decode() {
+                Ok(request) => self.handle(request).await,
+                Err(e) => RemoteSignerResponse::Error(e.to_string()),
+            };
+            let response = ExternIO::encode(response)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+            write_frame(&mut stream, &response.0).await?;
+        }
+    }
+
+    async fn accept_handshake(&self, stream: &mut impl Connection) -> std::io::Result<()> {
+        let mut client_challenge = [0; CHALLENGE_BYTES];
+        stream.read_exact(&mut client_challenge).await?;
+
+        let mut daemon_challenge = [0; CHALLENGE_BYTES];
+        OsRng.fill_bytes(&mut daemon_challenge);
+        let daemon_proof = self
+            .key
+            .proof(DAEMON_PERSONAL, &client_challenge, &daemon_challenge);
+        stream.write_all(&daemon_challenge).await?;
+        stream.write_all(&daemon_proof).await?;
+        stream.flush().await?;
+
+        let mut client_proof = [0; PROOF_BYTES];
+        stream.read_exact(&mut client_proof).await?;
+        let expected = self
+            .key
+            .proof(CLIENT_PERSONAL, &client_challenge, &daemon_challenge);
+        if !constant_time_eq(&client_proof, &expected) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "Remote signer client failed to authenticate",
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn handle(&self, request: RemoteSignerRequest) -> RemoteSignerResponse {
+        let result = match request {
+            RemoteSignerRequest::Prepare(cell_id) => {
+                self.signer
+                    .prepare(&cell_id)
+                    .await
+                    .map(|_| RemoteSignerResponse::Prepared {
+                        provenance: self.signer.get_provenance(&cell_id),
+                        cap_secret: self.signer.get_cap_secret(&cell_id),
+                    })
+            }
+            RemoteSignerRequest::PrepareForFn {
+                cell_id,
+                zome_name,
+                fn_name,
+            } => self
+                .signer
+                .prepare_for_fn(&cell_id, &zome_name, &fn_name)
+                .await
+                .map(|_| RemoteSignerResponse::Prepared {
+                    provenance: self
+                        .signer
+                        .get_provenance_for_fn(&cell_id, &zome_name, &fn_name),
+                    cap_secret: self
+                        .signer
+                        .get_cap_secret_for_fn(&cell_id, &zome_name, &fn_name),
+                }),
+            RemoteSignerRequest::Sign {
+                cell_id,
+                provenance,
+                data_to_sign,
+            } => self
+                .signer
+                .sign(&cell_id, provenance, data_to_sign.into())
+                .await
+                .map(RemoteSignerResponse::Signature),
+            RemoteSignerRequest::SignZomeCall {
+                signing_cell_id,
+                params,
+            } => match params.serialize_and_hash() {
+                Ok((_, hash)) => self
+                    .signer
+                    .sign_zome_call_params(&signing_cell_id, &params, hash.into())
+                    .await
+                    .map(RemoteSignerResponse::Signature),
+                Err(e) => Err(e.into()),
+            },
+        };
+
+        result.unwrap_or_else(|e| RemoteSignerResponse::Error(e.to_string()))
+    }
+}
+
+/// Wait before accepting again, so that errors which persist for a while don't spin the loop.
+async fn accept_failed(_error: std::io::Error) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(error = %_error, "Failed to accept remote signer connection");
+
+    tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+}
+
+async fn open_connection(
+    endpoint: &RemoteSignerEndpoint,
+    key: &RemoteSignerKey,
+) -> ConductorApiResult<Box<dyn Connection>> {
+    tokio::time::timeout(HANDSHAKE_TIMEOUT, connect_and_handshake(endpoint, key))
+        .await
+        .map_err(|_| {
+            ConductorApiError::RemoteSignerError(
+                "Remote signer daemon did not complete the handshake in time".to_string(),
+            )
+        })?
+}
+
+async fn connect_and_handshake(
+    endpoint: &RemoteSignerEndpoint,
+    key: &RemoteSignerKey,
+) -> ConductorApiResult<Box<dyn Connection>> {
+    let mut stream: Box<dyn Connection> = match endpoint {
+        RemoteSignerEndpoint::Tcp(addr) => {
+            if !addr.ip().is_loopback() {
+                return Err(ConductorApiError::RemoteSignerError(format!(
+                    "Remote signer address {addr} is not a loopback address"
+                )));
+            }
+            Box::new(TcpStream::connect(addr).await?)
+        }
+        #[cfg(unix)]
+        RemoteSignerEndpoint::Unix(path) => Box::new(tokio::net::UnixStream::connect(path).await?),
+    };
+
+    let mut client_challenge = [0; CHALLENGE_BYTES];
+    OsRng.fill_bytes(&mut client_challenge);
+    stream.write_all(&client_challenge).await?;
+    stream.flush().await?;
+
+    let mut daemon_challenge = [0; CHALLENGE_BYTES];
+    let mut daemon_proof = [0; PROOF_BYTES];
+    stream.read_exact(&mut daemon_challenge).await?;
+    stream.read_exact(&mut daemon_proof).await?;
+    let expected = key.proof(DAEMON_PERSONAL, &client_challenge, &daemon_challenge);
+    if !constant_time_eq(&daemon_proof, &expected) {
+        return Err(ConductorApiError::RemoteSignerError(
+            "Remote signer daemon failed to authenticate".to_string(),
+        ));
+    }
+
+    let client_proof = key.proof(CLIENT_PERSONAL, &client_challenge, &daemon_challenge);
+    stream.write_all(&client_proof).await?;
+    stream.flush().await?;
+
+    Ok(stream)
+}
+
+async fn read_frame(stream: &mut (impl AsyncRead + Unpin)) -> std::io::Result<Vec<u8>> {
+    let length = stream.read_u32().await? as usize;
+    if length > MAX_FRAME_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Frame of {length} bytes is too large"),
+        ));
+    }
+
+    let mut frame = vec![0; length];
+    stream.read_exact(&mut frame).await?;
+    Ok(frame)
+}
+
+async fn write_frame(stream: &mut (impl AsyncWrite + Unpin), frame: &[u8]) -> std::io::Result<()> {
+    if frame.len() > MAX_FRAME_BYTES {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!("Frame of {} bytes is too large", frame.len()),
+        ));
+    }
+
+    stream.write_u32(frame.len() as u32).await?;
+    stream.write_all(frame).await?;
+    stream.flush().await
+}
+
+fn constant_time_eq(a: &[u8; PROOF_BYTES], b: &[u8; PROOF_BYTES]) -> bool {
+    a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
@@ -0,0 +1,218 @@
+use super::{AgentSigner, DynAgentSigner};
+use anyhow::Result;
+use async_trait::async_trait;
+use holo_hash::{AgentPubKey, DnaHash};
+use holochain_conductor_api::{AppInfo, CellInfo};
+use holochain_zome_types::{
+    capability::CapSecret,
+    cell::CellId,
+    dependencies::holochain_integrity_types::Signature,
+    prelude::{FunctionName, RoleName, ZomeName},
+    zome_io::ZomeCallParams,
+};
+use parking_lot::RwLock;
+use std::{collections::HashMap, sync::Arc};
+
+#[derive(Default)]
+struct Routes {
+    by_cell: HashMap<CellId, DynAgentSigner>,
+    by_role: HashMap<RoleName, DynAgentSigner>,
+    by_dna: HashMap<DnaHash, DynAgentSigner>,
+    fallback: Option<DynAgentSigner>,
+    cell_roles: HashMap<CellId, RoleName>,
+}
+
+/// An [AgentSigner] which delegates to other signers depending on the cell being signed for.
+///
+/// This allows a single [AppWebsocket](crate::AppWebsocket) to use different signing strategies
+/// for different cells, for example a [ClientAgentSigner](crate::ClientAgentSigner) for some cells
+/// and a `LairAgentSigner` for others.
+///
+/// A signer is chosen for a cell by looking for, in order:
+/// 1. A signer routed to the cell id with [RoutingAgentSigner::route_cell].
+/// 2. A signer routed to the role of the cell with [RoutingAgentSigner::route_role]. Cells are
+///    matched to roles using the latest [AppInfo], which includes clone cells under the role they
+///    were cloned from. An [AppWebsocket](crate::AppWebsocket) using this signer keeps it up to
+///    date, otherwise it must be given with [RoutingAgentSigner::set_app_info].
+/// 3. A signer routed to the DNA hash of the cell with [RoutingAgentSigner::route_dna].
+/// 4. The fallback signer set with [RoutingAgentSigner::set_fallback].
+///
+/// Cloning this signer is cheap, and all clones share the same routes.
+#[derive(Clone, Default)]
+pub struct RoutingAgentSigner {
+    routes: Arc<RwLock<Routes>>,
+}
+
+impl std::fmt::Debug for RoutingAgentSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let routes = self.routes.read();
+        f.debug_struct("RoutingAgentSigner")
+            .field("cells", &routes.by_cell.keys().collect::<Vec<_>>())
+            .field("roles", &routes.by_role.keys().collect::<Vec<_>>())
+            .field("dnas", &routes.by_dna.keys().collect::<Vec<_>>())
+            .field("has_fallback", &routes.fallback.is_some())
+            .finish()
+    }
+}
+
+impl RoutingAgentSigner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use the given signer for the cell with this id.
+    pub fn route_cell(&self, cell_id: CellId, signer: DynAgentSigner) {
+        self.routes.write().by_cell.insert(cell_id, signer);
+    }
+
+    /// Use the given signer for all cells of this role, including clone cells.
+    ///
+    /// The role of a cell is looked up in the [AppInfo] given to
+    /// [RoutingAgentSigner::set_app_info].
+    pub fn route_role(&self, role_name: RoleName, signer: DynAgentSigner) {
+        self.routes.write().by_role.insert(role_name, signer);
+    }
+
+    /// Use the given signer for all cells with this DNA hash.
+    pub fn route_dna(&self, dna_hash: DnaHash, signer: DynAgentSigner) {
+        self.routes.write().by_dna.insert(dna_hash, signer);
+    }
+
+    /// Use the given signer for cells that do not match any other route.
+    pub fn set_fallback(&self, signer: DynAgentSigner) {
+        self.routes.write().fallback = Some(signer);
+    }
+
+    /// Set the app info that is used to find the role of a cell for [RoutingAgentSigner::route_role].
+    ///
+    /// An [AppWebsocket](crate::AppWebsocket) that this signer is used with calls this whenever it
+    /// refreshes its app info, including after clone cells are created through it. Call this
+    /// directly when the signer is used without a websocket, or to pick up clone cells created by
+    /// other clients before the websocket next refreshes.
+    pub fn set_app_info(&self, app_info: &AppInfo) {
+        let cell_roles = app_info
+            .cell_info
+            .iter()
+            .flat_map(|(role_name, cells)| {
+                cells.iter().filter_map(move |cell| match cell {
+                    CellInfo::Provisioned(c) => Some((c.cell_id.clone(), role_name.clone())),
+                    CellInfo::Cloned(c) => Some((c.cell_id.clone(), role_name.clone())),
+                    _ => None,
+                })
+            })
+            .collect();
+        self.routes.write().cell_roles = cell_roles;
+    }
+
+    fn signer_for(&self, cell_id: &CellId) -> Option<DynAgentSigner> {
+        let routes = self.routes.read();
+        routes
+            .by_cell
+            .get(cell_id)
+            .or_else(|| {
+                routes
+                    .cell_roles
+                    .get(cell_id)
+                    .and_then(|role_name| routes.by_role.get(role_name))
+            })
+            .or_else(|| routes.by_dna.get(cell_id.dna_hash()))
+            .or(routes.fallback.as_ref())
+            .cloned()
+    }
+}
+
+#[async_trait]
+impl AgentSigner for RoutingAgentSigner {
+    async fn prepare(&self, cell_id: &CellId) -> Result<()> {
+        match self.signer_for(cell_id) {
+            Some(signer) => signer.prepare(cell_id).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn prepare_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Result<()> {
+        match self.signer_for(cell_id) {
+            Some(signer) => signer.prepare_for_fn(cell_id, zome_name, fn_name).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn sign(
+        &self,
+        cell_id: &CellId,
+        provenance: AgentPubKey,
+        data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        let signer = self
+            .signer_for(cell_id)
+            .ok_or_else(|| anyhow::anyhow!("No signer routed for cell: {:?}", cell_id))?;
+        signer.sign(cell_id, provenance, data_to_sign).await
+    }
+
+    async fn sign_zome_call_params(
+        &self,
+        signing_cell_id: &CellId,
+        params: &ZomeCallParams,
+        data_to_sign: Arc<[u8]>,
+    ) -> Result<Signature> {
+        let signer = self
+            .signer_for(signing_cell_id)
+            .ok_or_else(|| anyhow::anyhow!("No signer routed for cell: {:?}", signing_cell_id))?;
+        signer
+            .sign_zome_call_params(signing_cell_id, params, data_to_sign)
+            .await
+    }
+
+    fn get_provenance(&self, cell_id: &CellId) -> Option<AgentPubKey> {
+        self.signer_for(cell_id)?.get_provenance(cell_id)
+    }
+
+    fn get_cap_secret(&self, cell_id: &CellId) -> Option<CapSecret> {
+        self.signer_for(cell_id)?.get_cap_secret(cell_id)
+    }
+
+    fn get_provenance_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<AgentPubKey> {
+        self.signer_for(cell_id)?
+            .get_provenance_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn get_cap_secret_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<CapSecret> {
+        self.signer_for(cell_id)?
+            .get_cap_secret_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn get_credentials_for_fn(
+        &self,
+        cell_id: &CellId,
+        zome_name: &ZomeName,
+        fn_name: &FunctionName,
+    ) -> Option<(AgentPubKey, Option<CapSecret>)> {
+        self.signer_for(cell_id)?
+            .get_credentials_for_fn(cell_id, zome_name, fn_name)
+    }
+
+    fn update_app_info(&self, app_info: &AppInfo) {
+        self.set_app_info(app_info);
+    }
+}
+
+impl From<RoutingAgentSigner> for DynAgentSigner {
+    fn from(signer: RoutingAgentSigner) -> Self {
+        Arc::new(signer)
+    }
+}
@@ -0,0 +1,109 @@
+use super::client_signing::SigningCredentials;
+use holo_hash::AgentPubKey;
+use holochain_zome_types::{capability::CAP_SECRET_BYTES, cell::CellId};
+use rand::{rngs::OsRng, RngCore};
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+const SIGNING_KEY_PERSONAL: &[u8] = b"hc_client_key";
+const CAP_SECRET_PERSONAL: &[u8] = b"hc_client_cap";
+
+/// A master seed from which [SigningCredentials] can be derived deterministically.
+///
+/// The signing key and cap secret for a cell are derived from the seed, the [CellId] and an index.
+/// The same seed, cell id and index always give the same credentials, so a client can be recovered
+/// from a backed up seed, and test fixtures can use known keys. Use a different index to derive
+/// more than one set of credentials for the same cell.
+///
+/// Use [AdminWebsocket::authorize_signing_credentials_from_seed](crate::AdminWebsocket::authorize_signing_credentials_from_seed)
+/// to derive credentials and grant them a capability. If the capability has already been granted,
+/// use [SigningKeySeed::derive_credentials] to recover the credentials without granting again.
+///
+/// Anyone who knows the seed can sign zome calls for any cell that derived credentials were
+/// granted for, so it must be kept secret. The seed is zeroized when dropped, and is deliberately not
+/// [Clone] so that it is not copied around in memory.
+pub struct SigningKeySeed([u8; 32]);
+
+impl Zeroize for SigningKeySeed {
+    fn zeroize(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl Drop for SigningKeySeed {
+    fn drop(&mut self) {
+        self.zeroize();
+    }
+}
+
+impl ZeroizeOnDrop for SigningKeySeed {}
+
+/// Custom debug implementation which won't attempt to print the seed
+impl std::fmt::Debug for SigningKeySeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SigningKeySeed").finish()
+    }
+}
+
+impl SigningKeySeed {
+    /// Use the given bytes as the master seed.
+    ///
+    /// The given array is moved into the seed, but copies of it held elsewhere are not zeroized.
+    pub fn from_bytes(seed: [u8; 32]) -> Self {
+        Self(seed)
+    }
+
+    /// Generate a new random master seed.
+    pub fn generate() -> Self {
+        let mut seed = Self([0; 32]);
+        OsRng.fill_bytes(&mut seed.0);
+        seed
+    }
+
+    /// The bytes of the master seed, so that it can be backed up.
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// Derive the signing credentials for a cell at the given index.
+    pub fn derive_credentials(&self, cell_id: &CellId, index: u32) -> SigningCredentials {
+        let mut key_seed = Zeroizing::new([0; ed25519_dalek::SECRET_KEY_LENGTH]);
+        self.derive(
+            SIGNING_KEY_PERSONAL,
+            cell_id,
+            index,
+            key_seed.as_mut_slice(),
+        );
+        let keypair = ed25519_dalek::SigningKey::from_bytes(&key_seed);
+        let signing_agent_key =
+            AgentPubKey::from_raw_32(keypair.verifying_key().as_bytes().to_vec());
+
+        let mut cap_secret = Zeroizing::new([0; CAP_SECRET_BYTES]);
+        self.derive(
+            CAP_SECRET_PERSONAL,
+            cell_id,
+            index,
+            cap_secret.as_mut_slice(),
+        );
+
+        SigningCredentials {
+            signing_agent_key,
+            keypair,
+            cap_secret: (*cap_secret).into(),
+        }
+    }
+
+    /// Keyed BLAKE2b of the derivation path, personalised for what is being derived, written to
+    /// `out`.
+    fn derive(&self, personal: &[u8], cell_id: &CellId, index: u32, out: &mut [u8]) {
+        let hash = blake2b_simd::Params::new()
+            .hash_length(out.len())
+            .key(&self.0)
+            .personal(personal)
+            .to_state()
+            .update(cell_id.dna_hash().get_raw_39())
+            .update(cell_id.agent_pubkey().get_raw_39())
+            .update(&index.to_be_bytes())
+            .finalize();
+        out.copy_from_slice(hash.as_bytes());
+    }
+}
@@ -0,0 +1,254 @@
+use super::{sign_error, sign_zome_call, DynAgentSigner};
+use crate::{ConductorApiError, ConductorApiResult};
+use holochain_conductor_api::ZomeCallParamsSigned;
+use holochain_nonce::{fresh_nonce, FRESH_NONCE_EXPIRES_AFTER};
+use holochain_zome_types::{
+    cell::CellId,
+    prelude::{FunctionName, SerializedBytesError, Timestamp, ZomeName},
+    zome_io::{ExternIO, ZomeCallParams},
+};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The reason that a signed zome call failed verification with [verify_zome_call].
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ZomeCallVerificationError {
+    #[error("Provenance is not a valid Ed25519 public key")]
+    InvalidProvenance,
+    #[error("Signature does not match the provenance")]
+    InvalidSignature,
+    #[error("Zome call expired at {0}")]
+    Expired(Timestamp),
+    #[error("Zome call expires too far in the future, at {0}")]
+    ExpiresTooLate(Timestamp),
+}
+
+/// A zome call that has been signed ahead of time, without a connection to a conductor.
+///
+/// This allows a zome call to be prepared and signed by a process that holds the signing key,
+/// saved with [SignedZomeCall::to_bytes], and later loaded with [SignedZomeCall::from_bytes] and
+/// submitted by a different process using
+/// [AppWebsocket::signed_call_zome](crate::AppWebsocket::signed_call_zome).
+///
+/// The call must be submitted before it expires. The expiry is set by the nonce in the
+/// [ZomeCallParams], which is normally created with
+/// [fresh_nonce](holochain_nonce::fresh_nonce) and is valid for a few minutes.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use holochain_client::{
+///     CellId, ClientAgentSigner, ExternIO, SignedZomeCall, SigningCredentials,
+/// };
+///
+/// async fn sign_for_later(cell_id: CellId, credentials: SigningCredentials) {
+///     let signer = ClientAgentSigner::default();
+///     signer.add_credentials(cell_id.clone(), credentials);
+///
+///     let signed = SignedZomeCall::create(
+///         cell_id,
+///         "foo".into(),
+///         "foo".into(),
+///         ExternIO::encode(()).unwrap(),
+///         signer.into(),
+///     )
+///     .await
+///     .unwrap();
+///     std::fs::write("call.bin", signed.to_bytes().unwrap()).unwrap();
+/// }
+///
+/// // Later, in another process
+/// let signed = SignedZomeCall::from_bytes(&std::fs::read("call.bin").unwrap()).unwrap();
+/// assert!(!signed.is_expired());
+/// ```
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "ZomeCallParamsSigned", into = "ZomeCallParamsSigned")]
+pub struct SignedZomeCall {
+    params: ZomeCallParams,
+    signed_params: ZomeCallParamsSigned,
+}
+
+/// Custom debug implementation which won't attempt to print the `cap_secret` or the payload
+impl std::fmt::Debug for SignedZomeCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SignedZomeCall")
+            .field("cell_id", &self.params.cell_id)
+            .field("zome_name", &self.params.zome_name)
+            .field("fn_name", &self.params.fn_name)
+            .field("provenance", &self.params.provenance)
+            .field("expires_at", &self.params.expires_at)
+            .finish()
+    }
+}
+
+impl SignedZomeCall {
+    /// Create and sign a call to a zome function with any [AgentSigner](crate::AgentSigner), in the
+    /// same way as [AppWebsocket::call_zome](crate::AppWebsocket::call_zome) does.
+    ///
+    /// The signer is prepared for the function first, so that signers which fetch or authorize
+    /// credentials when preparing can be used, and the provenance and cap secret are taken from
+    /// the credentials that the signer holds for the function. The call expires when a fresh nonce
+    /// would.
+    pub async fn create(
+        cell_id: CellId,
+        zome_name: ZomeName,
+        fn_name: FunctionName,
+        payload: ExternIO,
+        signer: DynAgentSigner,
+    ) -> ConductorApiResult<Self> {
+        signer
+            .prepare_for_fn(&cell_id, &zome_name, &fn_name)
+            .await
+            .map_err(|e| ConductorApiError::SignZomeCallError(e.to_string()))?;
+
+        let (provenance, cap_secret) = signer
+            .get_credentials_for_fn(&cell_id, &zome_name, &fn_name)
+            .ok_or(ConductorApiError::SignZomeCallError(
+                "Provenance not found".to_string(),
+            ))?;
+        let (nonce, expires_at) =
+            fresh_nonce(Timestamp::now()).map_err(ConductorApiError::FreshNonceError)?;
+
+        Self::sign(
+            ZomeCallParams {
+                provenance,
+                cap_secret,
+                cell_id,
+                zome_name,
+                fn_name,
+                payload,
+                expires_at,
+                nonce,
+            },
+            signer,
+        )
+        .await
+    }
+
+    /// Sign the given zome call parameters with any [AgentSigner](crate::AgentSigner).
+    ///
+    /// The signer is prepared for the function being called first, and must be able to sign for
+    /// the `provenance` of the params. Use [SignedZomeCall::create] to take the provenance and cap
+    /// secret from the signer instead.
+    pub async fn sign(params: ZomeCallParams, signer: DynAgentSigner) -> ConductorApiResult<Self> {
+        signer
+            .prepare_for_fn(&params.cell_id, &params.zome_name, &params.fn_name)
+            .await
+            .map_err(|e| ConductorApiError::SignZomeCallError(e.to_string()))?;
+
+        let signed_params = sign_zome_call(&params.cell_id, params.clone(), signer)
+            .await
+            .map_err(sign_error)?;
+
+        Ok(Self {
+            params,
+            signed_params,
+        })
+    }
+
+    /// The parameters that were signed.
+    pub fn params(&self) -> &ZomeCallParams {
+        &self.params
+    }
+
+    /// The time after which the conductor will reject this zome call.
+    pub fn expires_at(&self) -> Timestamp {
+        self.params.expires_at
+    }
+
+    /// Whether this zome call has expired and can no longer be submitted.
+    pub fn is_expired(&self) -> bool {
+        self.params.expires_at <= Timestamp::now()
+    }
+
+    /// Encode this signed zome call so that it can be written to a file or sent to another process.
+    pub fn to_bytes(&self) -> ConductorApiResult<Vec<u8>> {
+        Ok(ExternIO::encode(self.clone())?.0)
+    }
+
+    /// Decode a signed zome call that was encoded with [SignedZomeCall::to_bytes].
+    pub fn from_bytes(bytes: &[u8]) -> ConductorApiResult<Self> {
+        Ok(ExternIO(bytes.to_vec()).decode()?)
+    }
+
+    /// Check the signature and expiry of this zome call, as [verify_zome_call] does.
+    pub fn verify(&self, max_clock_skew: Duration) -> ConductorApiResult<()> {
+        verify_zome_call(&self.signed_params, max_clock_skew).map(|_| ())
+    }
+
+    /// Get the signed params, ready to be submitted with
+    /// [AppWebsocket::signed_call_zome](crate::AppWebsocket::signed_call_zome).
+    pub fn into_signed_params(self) -> ZomeCallParamsSigned {
+        self.signed_params
+    }
+}
+
+impl TryFrom<ZomeCallParamsSigned> for SignedZomeCall {
+    type Error = SerializedBytesError;
+
+    fn try_from(signed_params: ZomeCallParamsSigned) -> Result<Self, Self::Error> {
+        Ok(Self {
+            params: signed_params.bytes.decode()?,
+            signed_params,
+        })
+    }
+}
+
+impl From<SignedZomeCall> for ZomeCallParamsSigned {
+    fn from(signed: SignedZomeCall) -> Self {
+        signed.signed_params
+    }
+}
+
+/// Verify a signed zome call without a connection to a conductor, and return its decoded params.
+///
+/// This checks the same things that a conductor checks before running a zome call, so that a
+/// gateway which relays zome calls signed by others can reject bad calls before forwarding them:
+/// - The signature was made over the params by the private key of the `provenance`.
+/// - The call has not expired, and does not expire further in the future than a fresh nonce.
+///
+/// `max_clock_skew` is how far the clock of the signer may be from the local clock. Calls are
+/// accepted for this long after they expire.
+///
+/// It does not check whether the provenance is allowed to call the function, which only the
+/// conductor can check.
+pub fn verify_zome_call(
+    signed_params: &ZomeCallParamsSigned,
+    max_clock_skew: Duration,
+) -> ConductorApiResult<ZomeCallParams> {
+    let params: ZomeCallParams = signed_params.bytes.decode()?;
+
+    let public_key = <[u8; 32]>::try_from(params.provenance.get_raw_32())
+        .ok()
+        .and_then(|key| ed25519_dalek::VerifyingKey::from_bytes(&key).ok())
+        .ok_or_else(|| verification_error(ZomeCallVerificationError::InvalidProvenance))?;
+    let hash = blake2b_simd::Params::new()
+        .hash_length(32)
+        .hash(&signed_params.bytes.0);
+    let signature = ed25519_dalek::Signature::from_bytes(&signed_params.signature.0);
+    public_key
+        .verify_strict(hash.as_bytes(), &signature)
+        .map_err(|_| verification_error(ZomeCallVerificationError::InvalidSignature))?;
+
+    // The expiry comes from the signer, so it may be anywhere in the range of an i64
+    let now = Timestamp::now().as_micros();
+    let skew = i64::try_from(max_clock_skew.as_micros()).unwrap_or(i64::MAX);
+    let max_expiry = i64::try_from(FRESH_NONCE_EXPIRES_AFTER.as_micros()).unwrap_or(i64::MAX);
+    let expires_at = params.expires_at.as_micros();
+    if expires_at.saturating_add(skew) <= now {
+        return Err(verification_error(ZomeCallVerificationError::Expired(
+            params.expires_at,
+        )));
+    }
+    if expires_at.saturating_sub(skew) > now.saturating_add(max_expiry) {
+        return Err(verification_error(
+            ZomeCallVerificationError::ExpiresTooLate(params.expires_at),
+        ));
+    }
+
+    Ok(params)
+}
+
+fn verification_error(error: ZomeCallVerificationError) -> ConductorApiError {
+    ConductorApiError::ZomeCallVerificationFailed(error)
+}
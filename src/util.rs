@@ -1,3 +1,7 @@
+use holochain_zome_types::{
+    capability::GrantedFunctions,
+    prelude::{FunctionName, ZomeName},
+};
 use tokio::task::AbortHandle;
 
 pub(crate) struct AbortOnDropHandle(AbortHandle);
@@ -13,3 +17,91 @@ impl Drop for AbortOnDropHandle {
         self.0.abort();
     }
 }
+
+/// Whether a capability granted for `functions` can be used to call the given zome function.
+pub(crate) fn functions_cover(
+    functions: &GrantedFunctions,
+    zome_name: &ZomeName,
+    fn_name: &FunctionName,
+) -> bool {
+    match functions {
+        GrantedFunctions::All => true,
+        GrantedFunctions::Listed(functions) => {
+            functions.contains(&(zome_name.clone(), fn_name.clone()))
+        }
+    }
+}
+
+/// The number of functions that a capability granted for `functions` covers, where a grant for all
+/// functions is the widest.
+pub(crate) fn functions_width(functions: &GrantedFunctions) -> usize {
+    match functions {
+        GrantedFunctions::All => usize::MAX,
+        GrantedFunctions::Listed(functions) => functions.len(),
+    }
+}
+
+/// A short, secret-free name for an admin request, suitable for recording in spans.
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+pub(crate) fn admin_request_kind(request: &holochain_conductor_api::AdminRequest) -> &'static str {
+    use holochain_conductor_api::AdminRequest;
+
+    match request {
+        AdminRequest::AddAdminInterfaces(_) => "add_admin_interfaces",
+        AdminRequest::RegisterDna(_) => "register_dna",
+        AdminRequest::GetDnaDefinition(_) => "get_dna_definition",
+        AdminRequest::UpdateCoordinators(_) => "update_coordinators",
+        AdminRequest::InstallApp(_) => "install_app",
+        AdminRequest::UninstallApp { .. } => "uninstall_app",
+        AdminRequest::ListDnas => "list_dnas",
+        AdminRequest::GenerateAgentPubKey => "generate_agent_pub_key",
+        AdminRequest::RevokeAgentKey(_) => "revoke_agent_key",
+        AdminRequest::ListCellIds => "list_cell_ids",
+        AdminRequest::ListApps { .. } => "list_apps",
+        AdminRequest::EnableApp { .. } => "enable_app",
+        AdminRequest::DisableApp { .. } => "disable_app",
+        AdminRequest::AttachAppInterface { .. } => "attach_app_interface",
+        AdminRequest::ListAppInterfaces => "list_app_interfaces",
+        AdminRequest::DumpState { .. } => "dump_state",
+        AdminRequest::DumpConductorState => "dump_conductor_state",
+        AdminRequest::DumpFullState { .. } => "dump_full_state",
+        AdminRequest::DumpNetworkMetrics { .. } => "dump_network_metrics",
+        AdminRequest::DumpNetworkStats => "dump_network_stats",
+        AdminRequest::AddAgentInfo { .. } => "add_agent_info",
+        AdminRequest::AgentInfo { .. } => "agent_info",
+        AdminRequest::GraftRecords { .. } => "graft_records",
+        AdminRequest::GrantZomeCallCapability(_) => "grant_zome_call_capability",
+        AdminRequest::ListCapabilityGrants { .. } => "list_capability_grants",
+        AdminRequest::DeleteCloneCell(_) => "delete_clone_cell",
+        AdminRequest::StorageInfo => "storage_info",
+        AdminRequest::IssueAppAuthenticationToken(_) => "issue_app_authentication_token",
+        AdminRequest::RevokeAppAuthenticationToken(_) => "revoke_app_authentication_token",
+        // Requests behind the unstable features of `holochain_conductor_api`, which can't be named
+        // unless those features are enabled
+        #[allow(unreachable_patterns)]
+        _ => "unstable",
+    }
+}
+
+/// A short, secret-free name for an app request, suitable for recording in spans.
+#[cfg(any(feature = "tracing", feature = "metrics"))]
+pub(crate) fn app_request_kind(request: &holochain_conductor_api::AppRequest) -> &'static str {
+    use holochain_conductor_api::AppRequest;
+
+    match request {
+        AppRequest::AppInfo => "app_info",
+        AppRequest::CallZome(_) => "call_zome",
+        AppRequest::ProvideMemproofs(_) => "provide_memproofs",
+        AppRequest::EnableApp => "enable_app",
+        AppRequest::CreateCloneCell(_) => "create_clone_cell",
+        AppRequest::DisableCloneCell(_) => "disable_clone_cell",
+        AppRequest::EnableCloneCell(_) => "enable_clone_cell",
+        AppRequest::ListWasmHostFunctions => "list_wasm_host_functions",
+        AppRequest::DumpNetworkStats => "dump_network_stats",
+        AppRequest::DumpNetworkMetrics { .. } => "dump_network_metrics",
+        // Requests behind the unstable features of `holochain_conductor_api`, which can't be named
+        // unless those features are enabled
+        #[allow(unreachable_patterns)]
+        _ => "unstable",
+    }
+}
@@ -2,14 +2,16 @@ use holochain::prelude::{DnaModifiersOpt, RoleSettings, YamlProperties};
 use holochain::test_utils::itertools::Itertools;
 use holochain::{prelude::AppBundleSource, sweettest::SweetConductor};
 use holochain_client::{
-    AdminWebsocket, AppWebsocket, AuthorizeSigningCredentialsPayload, ClientAgentSigner,
-    InstallAppPayload, InstalledAppId,
+    AdminWebsocket, AgentSigner, AppWebsocket, AuthorizeSigningCredentialsPayload,
+    ClientAgentSigner, ConductorApiError, ConnectAppOptions, CredentialRotator, GrantedFunctions,
+    InstallAndConnectOptions, InstallAppPayload, InstalledAppId, SigningKeySeed,
 };
 use holochain_conductor_api::{CellInfo, StorageBlob};
 use holochain_types::websocket::AllowedOrigins;
-use holochain_zome_types::prelude::ExternIO;
+use holochain_zome_types::capability::{CapSecret, CAP_SECRET_BYTES};
+use holochain_zome_types::prelude::{CellId, DnaHash, ExternIO};
 use std::net::{Ipv4Addr, SocketAddr};
-use std::{collections::HashMap, path::PathBuf};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
 
 const ROLE_NAME: &str = "foo";
 
@@ -396,3 +398,912 @@ async fn connect_multiple_addresses() {
     let apps = admin_ws.list_apps(None).await.unwrap();
     assert!(apps.is_empty());
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn signing_credentials_from_seed() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let cell_id = match installed_app.cell_info.get(ROLE_NAME).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    let seed = SigningKeySeed::from_bytes([1; 32]);
+    let credentials = admin_ws
+        .authorize_signing_credentials_from_seed(
+            &seed,
+            0,
+            AuthorizeSigningCredentialsPayload {
+                cell_id: cell_id.clone(),
+                functions: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    // The same seed and path always derive the same credentials, other indexes do not
+    let recovered = SigningKeySeed::from_bytes(*seed.as_bytes()).derive_credentials(&cell_id, 0);
+    assert_eq!(recovered.signing_agent_key, credentials.signing_agent_key);
+    assert_eq!(recovered.cap_secret, credentials.cap_secret);
+    let other_index = seed.derive_credentials(&cell_id, 1);
+    assert_ne!(other_index.signing_agent_key, credentials.signing_agent_key);
+
+    // Recovered credentials can be used without granting them again
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let issued_token = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let signer = ClientAgentSigner::default();
+    signer.add_credentials(cell_id.clone(), recovered);
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        issued_token.token,
+        signer.into(),
+    )
+    .await
+    .unwrap();
+
+    let response = app_ws
+        .call_zome(
+            cell_id.into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn authorize_signing_credentials_for_app() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let signer = ClientAgentSigner::default();
+    let authorized = admin_ws
+        .authorize_signing_credentials_for_app(&installed_app, None, &signer)
+        .await
+        .unwrap();
+    assert_eq!(1, authorized.len());
+    assert!(signer.has_credentials(&authorized[0]));
+
+    // Cells that already have credentials are skipped
+    let authorized = admin_ws
+        .authorize_signing_credentials_for_app(&installed_app, None, &signer)
+        .await
+        .unwrap();
+    assert!(authorized.is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn scoped_signing_credentials() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let cell_id = match installed_app.cell_info.get(ROLE_NAME).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    // Grant one credential for `foo` only, and one for both `foo` and `bar`
+    let signer = ClientAgentSigner::default();
+    let mut provenances = Vec::new();
+    for fn_names in [vec!["foo"], vec!["foo", "bar"]] {
+        let functions = GrantedFunctions::Listed(
+            fn_names
+                .into_iter()
+                .map(|fn_name| ("foo".into(), fn_name.into()))
+                .collect(),
+        );
+        let credentials = admin_ws
+            .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+                cell_id: cell_id.clone(),
+                functions: Some(functions.clone()),
+            })
+            .await
+            .unwrap();
+        provenances.push(credentials.signing_agent_key.clone());
+        signer.add_scoped_credentials(cell_id.clone(), functions, credentials);
+    }
+
+    // The narrowest credential covering each function is chosen
+    assert_eq!(
+        signer.get_provenance_for_fn(&cell_id, &"foo".into(), &"foo".into()),
+        Some(provenances[0].clone())
+    );
+    assert_eq!(
+        signer.get_provenance_for_fn(&cell_id, &"foo".into(), &"bar".into()),
+        Some(provenances[1].clone())
+    );
+    assert_eq!(
+        signer.get_provenance_for_fn(&cell_id, &"foo".into(), &"emitter".into()),
+        None
+    );
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let issued_token = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        issued_token.token,
+        signer.into(),
+    )
+    .await
+    .unwrap();
+
+    for fn_name in ["foo", "bar"] {
+        let response = app_ws
+            .call_zome(
+                cell_id.clone().into(),
+                "foo".into(),
+                fn_name.into(),
+                ExternIO::encode(()).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.decode::<String>().unwrap(), fn_name);
+    }
+
+    // No credential covers `emitter`
+    let err = app_ws
+        .call_zome(
+            cell_id.into(),
+            "foo".into(),
+            "emitter".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::SignZomeCallError(_)));
+}
+
+#[cfg(feature = "lair_signing")]
+#[tokio::test(flavor = "multi_thread")]
+async fn lair_signing_credentials() {
+    use holochain_client::LairAgentSigner;
+    use std::sync::Arc;
+
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let cell_id = match installed_app.cell_info.get(ROLE_NAME).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    // Create the signing key in the conductor's Lair
+    let lair_client = conductor.keystore().lair_client();
+    let credentials = admin_ws
+        .authorize_lair_signing_credentials(
+            &lair_client,
+            AuthorizeSigningCredentialsPayload {
+                cell_id: cell_id.clone(),
+                functions: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert_ne!(credentials.signing_agent_key, installed_app.agent_pub_key);
+
+    let mut signer = LairAgentSigner::new(Arc::new(lair_client));
+    signer.add_signing_credentials(cell_id.clone(), credentials.clone());
+    assert_eq!(
+        signer.get_cap_secret(&cell_id),
+        Some(credentials.cap_secret)
+    );
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let issued_token = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        issued_token.token,
+        signer.into(),
+    )
+    .await
+    .unwrap();
+
+    let response = app_ws
+        .call_zome(
+            cell_id.into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+}
+
+#[cfg(feature = "lair_signing")]
+#[tokio::test(flavor = "multi_thread")]
+async fn lair_agent_signer_connect() {
+    use holochain::sweettest::{DynSweetRendezvous, SweetConductorConfig};
+    use holochain_client::LairAgentSigner;
+    use holochain_conductor_api::conductor::{ConductorConfig, KeystoreConfig};
+    use lair_keystore_api::{
+        config::LairServerConfigInner, dependencies::hc_seed_bundle::dependencies::sodoken,
+    };
+    use std::sync::{Arc, Mutex};
+
+    const PASSPHRASE: &[u8] = b"passphrase";
+
+    // Run the conductor against a Lair server that is reachable over IPC, as a conductor with an
+    // in process Lair would be
+    let lair_root =
+        std::env::temp_dir().join(format!("holochain_client_lair_{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&lair_root);
+    std::fs::create_dir_all(&lair_root).unwrap();
+    let keystore = holochain_keystore::lair_keystore::spawn_lair_keystore_in_proc(
+        &lair_root.join("lair-keystore-config.yaml"),
+        Arc::new(Mutex::new(sodoken::LockedArray::from(PASSPHRASE.to_vec()))),
+    )
+    .await
+    .unwrap();
+    let conductor = SweetConductor::create_with_defaults(
+        SweetConductorConfig::standard(),
+        Some(keystore),
+        None::<DynSweetRendezvous>,
+    )
+    .await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let cell_id = match installed_app.cell_info.get(ROLE_NAME).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    // Connect with the connection URL from the Lair config
+    let lair_config = LairServerConfigInner::from_bytes(
+        &std::fs::read(lair_root.join("lair-keystore-config.yaml")).unwrap(),
+    )
+    .unwrap();
+    let mut url_signer = LairAgentSigner::connect(lair_config.connection_url.as_str(), PASSPHRASE)
+        .await
+        .unwrap();
+    url_signer.add_credentials_for_app(&installed_app);
+    assert_eq!(
+        url_signer.get_provenance(&cell_id),
+        Some(installed_app.agent_pub_key.clone())
+    );
+    assert_eq!(url_signer.get_cap_secret(&cell_id), None);
+
+    // Connect with a conductor config that runs Lair in process
+    let config_path = lair_root.join("conductor-config.yaml");
+    std::fs::write(
+        &config_path,
+        serde_yaml::to_string(&ConductorConfig {
+            keystore: KeystoreConfig::LairServerInProc {
+                lair_root: Some(lair_root.clone().into()),
+            },
+            ..Default::default()
+        })
+        .unwrap(),
+    )
+    .unwrap();
+    let mut config_signer =
+        LairAgentSigner::connect_from_conductor_config(&config_path, PASSPHRASE)
+            .await
+            .unwrap();
+    config_signer.add_credentials_for_app(&installed_app);
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    for signer in [url_signer, config_signer] {
+        let issued_token = admin_ws
+            .issue_app_auth_token(app_id.clone().into())
+            .await
+            .unwrap();
+        let app_ws = AppWebsocket::connect(
+            (Ipv4Addr::LOCALHOST, app_ws_port),
+            issued_token.token,
+            signer.into(),
+        )
+        .await
+        .unwrap();
+
+        let response = app_ws
+            .call_zome(
+                cell_id.clone().into(),
+                "foo".into(),
+                "foo".into(),
+                ExternIO::encode(()).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.decode::<String>().unwrap(), "foo");
+    }
+
+    // A conductor config that doesn't use Lair is rejected
+    std::fs::write(
+        &config_path,
+        serde_yaml::to_string(&ConductorConfig {
+            keystore: KeystoreConfig::DangerTestKeystore,
+            ..Default::default()
+        })
+        .unwrap(),
+    )
+    .unwrap();
+    let err = LairAgentSigner::connect_from_conductor_config(&config_path, PASSPHRASE)
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, ConductorApiError::KeystoreError(_)));
+
+    let _ = std::fs::remove_dir_all(lair_root);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rotate_signing_credentials() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let cell_id = match installed_app.cell_info.get(ROLE_NAME).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    let signer = ClientAgentSigner::default();
+    let credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: cell_id.clone(),
+            functions: None,
+        })
+        .await
+        .unwrap();
+    let old_key = credentials.signing_agent_key.clone();
+    signer.add_credentials(cell_id.clone(), credentials);
+
+    // Credentials for a cell that doesn't exist can't be rotated, but don't stop the others
+    let missing_cell_id = CellId::new(
+        DnaHash::from_raw_32(vec![0; 32]),
+        cell_id.agent_pubkey().clone(),
+    );
+    let credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: cell_id.clone(),
+            functions: None,
+        })
+        .await
+        .unwrap();
+    signer.add_credentials(missing_cell_id.clone(), credentials);
+
+    let rotator = CredentialRotator::new(admin_ws.clone(), signer.clone());
+    let report = rotator.rotate_all().await;
+    assert!(!report.is_complete());
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, missing_cell_id);
+    let superseded = report.superseded;
+    assert_eq!(superseded.len(), 1);
+    assert_eq!(superseded[0].cell_id, cell_id);
+    assert_eq!(superseded[0].signing_agent_key, old_key);
+
+    // New calls use the new key, and calls prepared with the old key can still be signed
+    let new_key = signer.get_provenance(&cell_id).unwrap();
+    assert_eq!(superseded[0].replaced_by, new_key);
+    assert_ne!(new_key, old_key);
+    signer
+        .sign(&cell_id, old_key, vec![1, 2, 3].into())
+        .await
+        .unwrap();
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let issued_token = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        issued_token.token,
+        signer.into(),
+    )
+    .await
+    .unwrap();
+
+    let response = app_ws
+        .call_zome(
+            cell_id.into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+
+    // An interval that can't be represented as a point in time never rotates, rather than panicking
+    let _handle = rotator.clone().schedule(Duration::MAX, |_| {}).unwrap();
+
+    let err = rotator.schedule(Duration::ZERO, |_| {}).unwrap_err();
+    assert!(matches!(err, ConductorApiError::InvalidRotationInterval));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn rotate_signing_credentials_during_calls() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let cell_id = match installed_app.cell_info.get(ROLE_NAME).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    let signer = ClientAgentSigner::default();
+    let credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: cell_id.clone(),
+            functions: None,
+        })
+        .await
+        .unwrap();
+    signer.add_credentials(cell_id.clone(), credentials);
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let issued_token = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        issued_token.token,
+        signer.clone().into(),
+    )
+    .await
+    .unwrap();
+
+    // Every call succeeds while the credentials are rotated underneath it
+    let rotator = CredentialRotator::new(admin_ws.clone(), signer);
+    let calls = futures::future::join_all((0..20).map(|_| {
+        app_ws.call_zome(
+            cell_id.clone().into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+    }));
+    let rotations = async {
+        for _ in 0..5 {
+            rotator
+                .rotate(&cell_id, GrantedFunctions::All)
+                .await
+                .unwrap()
+                .unwrap();
+        }
+    };
+    let (responses, _) = tokio::join!(calls, rotations);
+    for response in responses {
+        assert_eq!(response.unwrap().decode::<String>().unwrap(), "foo");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn build_cap_grant() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let cell_id = match installed_app.cell_info.get(ROLE_NAME).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    // Grants without a chosen access are rejected
+    let err = admin_ws
+        .build_cap_grant(cell_id.clone())
+        .secret(CapSecret::from([7; CAP_SECRET_BYTES]))
+        .function("foo", "foo")
+        .grant()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::InvalidCapGrant(_)));
+
+    // The secret is not included in the debug output of the builder
+    let secret = CapSecret::from([7; CAP_SECRET_BYTES]);
+    let builder = admin_ws
+        .build_cap_grant(cell_id.clone())
+        .transferable()
+        .secret(secret);
+    assert!(!format!("{builder:?}").contains(&format!("{secret:?}")));
+
+    // Zomes that are not in the DNA are rejected before the grant is sent
+    let err = admin_ws
+        .build_cap_grant(cell_id.clone())
+        .transferable()
+        .function("not_a_zome", "foo")
+        .grant()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::InvalidCapGrant(_)));
+
+    let err = admin_ws
+        .build_cap_grant(cell_id.clone())
+        .transferable()
+        .functions(Vec::<(&str, &str)>::new())
+        .grant()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::InvalidCapGrant(_)));
+
+    // Assign a grant for `foo` to two signing keys
+    let seed = SigningKeySeed::generate();
+    let credentials = seed.derive_credentials(&cell_id, 0);
+    let other_credentials = seed.derive_credentials(&cell_id, 1);
+    let cap_grant = admin_ws
+        .build_cap_grant(cell_id.clone())
+        .tag("foo-only")
+        .assignees([
+            credentials.signing_agent_key.clone(),
+            other_credentials.signing_agent_key.clone(),
+        ])
+        .secret(credentials.cap_secret)
+        .function("foo", "foo")
+        .grant()
+        .await
+        .unwrap();
+    assert_eq!(cap_grant.tag, "foo-only");
+    assert_eq!(
+        cap_grant.functions,
+        GrantedFunctions::Listed([("foo".into(), "foo".into())].into_iter().collect())
+    );
+
+    let signer = ClientAgentSigner::default();
+    signer.add_credentials(cell_id.clone(), credentials);
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let issued_token = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        issued_token.token,
+        signer.into(),
+    )
+    .await
+    .unwrap();
+
+    let response = app_ws
+        .call_zome(
+            cell_id.clone().into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+
+    // Functions that were not granted cannot be called
+    app_ws
+        .call_zome(
+            cell_id.into(),
+            "foo".into(),
+            "bar".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap_err();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn install_and_connect() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    let app = admin_ws
+        .install_and_connect(
+            AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            InstallAndConnectOptions {
+                installed_app_id: Some("test-app".into()),
+                allowed_origins: AllowedOrigins::from("my_cli_app".to_string()),
+                origin: Some("my_cli_app".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(app.app_info.installed_app_id, "test-app");
+
+    let cell_id = match app.app_info.cell_info.get(ROLE_NAME).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+    assert!(app.signer.has_credentials(&cell_id));
+
+    let response = app
+        .app_ws
+        .call_zome(
+            cell_id.into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+
+    // The app is uninstalled again if connecting fails
+    admin_ws
+        .install_and_connect(
+            AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            InstallAndConnectOptions {
+                installed_app_id: Some("other-app".into()),
+                allowed_origins: AllowedOrigins::from("my_cli_app".to_string()),
+                origin: Some("not_my_cli_app".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+    let apps = admin_ws.list_apps(None).await.unwrap();
+    assert_eq!(apps.len(), 1);
+    assert_eq!(apps[0].installed_app_id, "test-app");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn connect_app() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+
+    // The existing app interface is used
+    let app = admin_ws
+        .connect_app(app_id.clone(), ConnectAppOptions::default())
+        .await
+        .unwrap();
+    let app_interfaces = admin_ws.list_app_interfaces().await.unwrap();
+    assert_eq!(app_interfaces.len(), 1);
+    assert_eq!(app_interfaces[0].port, app_ws_port);
+
+    let cell_id = match app.app_info.cell_info.get(ROLE_NAME).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+    let response = app
+        .app_ws
+        .call_zome(
+            cell_id.clone().into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+
+    // Signing credentials are only authorized when requested
+    let app = admin_ws
+        .connect_app(
+            app_id,
+            ConnectAppOptions {
+                authorize_signing_credentials: false,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert!(!app.signer.has_credentials(&cell_id));
+}
@@ -3,8 +3,11 @@ use holochain::{
     sweettest::SweetConductor,
 };
 use holochain_client::{
-    AdminWebsocket, AppWebsocket, AuthorizeSigningCredentialsPayload, ClientAgentSigner,
-    InstallAppPayload, InstalledAppId,
+    AdminWebsocket, AgentSigner, AppWebsocket, ApprovalAgentSigner, ApprovalDecision,
+    ApprovalRequest, AuthorizeSigningCredentialsPayload, AutoAuthorizingAgentSigner,
+    CapabilityClaim, CapabilityWallet, ClientAgentSigner, ConductorApiError, InstallAppPayload,
+    InstalledAppId, PolicyAgentSigner, PolicyViolation, RateLimit, SignedZomeCall, SigningPolicy,
+    Timestamp, ZomeCallParams,
 };
 use holochain_conductor_api::{AppInfoStatus, CellInfo, IssueAppAuthenticationTokenPayload};
 use holochain_types::{
@@ -19,6 +22,7 @@ use std::{
     collections::HashMap,
     path::PathBuf,
     sync::{Arc, Barrier},
+    time::Duration,
 };
 
 #[tokio::test(flavor = "multi_thread")]
@@ -483,3 +487,520 @@ async fn dump_network_metrics() {
 
     assert_eq!(1, metrics.len());
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn offline_signed_zome_call() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let cells = installed_app.cell_info.into_values().next().unwrap();
+    let cell_id = match cells[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    // Sign a zome call without an app connection
+    let signer = ClientAgentSigner::default();
+    let credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: cell_id.clone(),
+            functions: None,
+        })
+        .await
+        .unwrap();
+    signer.add_credentials(cell_id.clone(), credentials);
+
+    let (nonce, expires_at) = holochain_nonce::fresh_nonce(Timestamp::now()).unwrap();
+    let params = ZomeCallParams {
+        provenance: signer.get_provenance(&cell_id).unwrap(),
+        cap_secret: signer.get_cap_secret(&cell_id),
+        cell_id: cell_id.clone(),
+        zome_name: "foo".into(),
+        fn_name: "foo".into(),
+        payload: ExternIO::encode(()).unwrap(),
+        expires_at,
+        nonce,
+    };
+    let signed = SignedZomeCall::sign(params, signer.into()).await.unwrap();
+    let exported = signed.to_bytes().unwrap();
+
+    // Submit it from a separate connection, with a signer that has no credentials
+    let imported = SignedZomeCall::from_bytes(&exported).unwrap();
+    assert_eq!(imported.params().cell_id, cell_id);
+    assert_eq!(imported.expires_at(), expires_at);
+    assert!(!imported.is_expired());
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let token_issued = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        token_issued.token,
+        ClientAgentSigner::default().into(),
+    )
+    .await
+    .unwrap();
+
+    let response = app_ws
+        .signed_call_zome(imported.into_signed_params())
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+
+    // A signer which authorizes credentials when it is prepared can create signed calls too
+    let signed = SignedZomeCall::create(
+        cell_id,
+        "foo".into(),
+        "foo".into(),
+        ExternIO::encode(()).unwrap(),
+        AutoAuthorizingAgentSigner::new(admin_ws.clone(), None).into(),
+    )
+    .await
+    .unwrap();
+    let response = app_ws
+        .signed_call_zome(signed.into_signed_params())
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn signing_policy() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let cells = installed_app.cell_info.into_values().next().unwrap();
+    let cell_id = match cells[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    let client_signer = ClientAgentSigner::default();
+    let credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: cell_id.clone(),
+            functions: None,
+        })
+        .await
+        .unwrap();
+    client_signer.add_credentials(cell_id.clone(), credentials);
+
+    // Only allow `foo`, with small payloads, once per hour
+    let signer = PolicyAgentSigner::new(
+        client_signer.into(),
+        SigningPolicy {
+            allowed_functions: Some([("foo".into(), "foo".into())].into()),
+            max_payload_bytes: Some(16),
+            rate_limit: Some(RateLimit {
+                max_signatures: 1,
+                period: Duration::from_secs(3600),
+            }),
+            ..Default::default()
+        },
+    );
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let token_issued = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        token_issued.token,
+        signer.into(),
+    )
+    .await
+    .unwrap();
+
+    let err = app_ws
+        .call_zome(
+            cell_id.clone().into(),
+            "foo".into(),
+            "bar".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ConductorApiError::SigningPolicyViolation(PolicyViolation::FunctionNotAllowed(_, _))
+    ));
+
+    let err = app_ws
+        .call_zome(
+            cell_id.clone().into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(vec![0u8; 32]).unwrap(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ConductorApiError::SigningPolicyViolation(PolicyViolation::PayloadTooLarge { .. })
+    ));
+
+    // Refused calls do not count towards the rate limit
+    let response = app_ws
+        .call_zome(
+            cell_id.clone().into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+
+    let err = app_ws
+        .call_zome(
+            cell_id.into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ConductorApiError::SigningPolicyViolation(PolicyViolation::RateLimited)
+    ));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn approval_signer() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let cells = installed_app.cell_info.into_values().next().unwrap();
+    let cell_id = match cells[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    let client_signer = ClientAgentSigner::default();
+    let credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: cell_id.clone(),
+            functions: None,
+        })
+        .await
+        .unwrap();
+    client_signer.add_credentials(cell_id.clone(), credentials);
+
+    // Approve `foo` for a minute, and reject everything else
+    let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let signer = ApprovalAgentSigner::new(client_signer.into(), {
+        let requests = requests.clone();
+        move |request: ApprovalRequest| {
+            let requests = requests.clone();
+            async move {
+                let decision = if request.fn_name.0 == "foo" {
+                    ApprovalDecision::ApproveFor(Duration::from_secs(60))
+                } else {
+                    ApprovalDecision::Reject
+                };
+                requests.lock().unwrap().push(request);
+                decision
+            }
+        }
+    });
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let token_issued = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        token_issued.token,
+        signer.into(),
+    )
+    .await
+    .unwrap();
+
+    // The decision for `foo` is remembered, so approval is only requested once
+    for _ in 0..2 {
+        let response = app_ws
+            .call_zome(
+                cell_id.clone().into(),
+                "foo".into(),
+                "foo".into(),
+                ExternIO::encode(()).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.decode::<String>().unwrap(), "foo");
+    }
+
+    let err = app_ws
+        .call_zome(
+            cell_id.clone().into(),
+            "foo".into(),
+            "bar".into(),
+            ExternIO::encode("hello").unwrap(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::SigningRejected(_)));
+
+    // Previews of large payloads are limited
+    let payloads = [
+        // Byte strings are shown as their length and a hex prefix
+        ExternIO::encode(ExternIO(vec![0xab; 100])).unwrap(),
+        // Long strings are truncated
+        ExternIO::encode("x".repeat(10_000)).unwrap(),
+        // Deeply nested arrays are elided, here 100 arrays of one element around a nil
+        ExternIO([vec![0x91; 100], vec![0xc0]].concat()),
+    ];
+    for payload in payloads {
+        let err = app_ws
+            .call_zome(cell_id.clone().into(), "foo".into(), "bar".into(), payload)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ConductorApiError::SigningRejected(_)));
+    }
+
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 5);
+    assert_eq!(requests[0].fn_name, "foo".into());
+    assert_eq!(requests[1].fn_name, "bar".into());
+    assert_eq!(requests[1].payload_preview, "\"hello\"");
+    assert_eq!(
+        requests[2].payload_preview,
+        "<100 bytes: abababababababab...>"
+    );
+    assert!(requests[3].payload_preview.len() < 2_000);
+    assert!(requests[3].payload_preview.ends_with("..."));
+    assert_eq!(
+        requests[4].payload_preview,
+        format!("{}...{}", "[".repeat(9), "]".repeat(9))
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn delegated_zome_call() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Install the test app for two agents
+    let mut cell_ids = Vec::new();
+    for app_id in ["alice-app", "bob-app"] {
+        let installed_app = admin_ws
+            .install_app(InstallAppPayload {
+                agent_key: None,
+                installed_app_id: Some(app_id.into()),
+                network_seed: None,
+                roles_settings: None,
+                source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+                ignore_genesis_failure: false,
+                allow_throwaway_random_agent_key: false,
+            })
+            .await
+            .unwrap();
+        admin_ws.enable_app(app_id.into()).await.unwrap();
+
+        let cells = installed_app.cell_info.into_values().next().unwrap();
+        cell_ids.push(match cells[0].clone() {
+            CellInfo::Provisioned(c) => c.cell_id,
+            _ => panic!("Invalid cell type"),
+        });
+    }
+    let (alice_cell_id, bob_cell_id) = (cell_ids[0].clone(), cell_ids[1].clone());
+
+    // Alice grants a capability for `foo`, and gives the secret to Bob
+    let cap_grant = admin_ws
+        .build_cap_grant(alice_cell_id.clone())
+        .tag("bob")
+        .transferable()
+        .function("foo", "foo")
+        .grant()
+        .await
+        .unwrap();
+    let wallet = CapabilityWallet::default();
+    wallet.add_claim(
+        alice_cell_id.agent_pubkey().clone(),
+        CapabilityClaim::from_grant(&cap_grant).unwrap(),
+    );
+
+    // Bob calls Alice's cell as himself
+    let signer = ClientAgentSigner::default();
+    let credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: bob_cell_id.clone(),
+            functions: None,
+        })
+        .await
+        .unwrap();
+    signer.add_credentials(bob_cell_id.clone(), credentials);
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let token_issued = admin_ws
+        .issue_app_auth_token("bob-app".to_string().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        token_issued.token,
+        signer.clone().into(),
+    )
+    .await
+    .unwrap();
+
+    let response = app_ws
+        .call_zome_with_claim(
+            &wallet,
+            alice_cell_id.clone(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+
+    // Functions that Bob has no claim for are not called
+    let err = app_ws
+        .call_zome_with_claim(
+            &wallet,
+            alice_cell_id.clone(),
+            "foo".into(),
+            "bar".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::CapClaimNotFound(_)));
+
+    // Bob's calls are made with his signing key, so a grant assigned to his agent key is not used
+    let cap_grant = admin_ws
+        .build_cap_grant(alice_cell_id.clone())
+        .tag("bob-agent")
+        .assigned_to(bob_cell_id.agent_pubkey().clone())
+        .function("foo", "bar")
+        .grant()
+        .await
+        .unwrap();
+    wallet.add_claim(
+        alice_cell_id.agent_pubkey().clone(),
+        CapabilityClaim::from_grant(&cap_grant).unwrap(),
+    );
+    let err = app_ws
+        .call_zome_with_claim(
+            &wallet,
+            alice_cell_id.clone(),
+            "foo".into(),
+            "bar".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::CapClaimNotFound(_)));
+
+    // A grant assigned to Bob's signing key is used
+    let cap_grant = admin_ws
+        .build_cap_grant(alice_cell_id.clone())
+        .tag("bob-signing-key")
+        .assigned_to(signer.get_provenance(&bob_cell_id).unwrap())
+        .function("foo", "bar")
+        .grant()
+        .await
+        .unwrap();
+    wallet.add_claim(
+        alice_cell_id.agent_pubkey().clone(),
+        CapabilityClaim::from_grant(&cap_grant).unwrap(),
+    );
+    let response = app_ws
+        .call_zome_with_claim(
+            &wallet,
+            alice_cell_id,
+            "foo".into(),
+            "bar".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "bar");
+}
@@ -0,0 +1,172 @@
+use holochain::{
+    prelude::{AppBundle, AppBundleSource, AppManifest, DnaBundle},
+    sweettest::SweetConductor,
+};
+use holochain_client::{
+    inspect_app_bundle, inspect_bundle_file, AdminWebsocket, BundleInfo, ConductorApiError,
+    InspectOptions, InstallAppPayload,
+};
+use holochain_conductor_api::CellInfo;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+
+const ROLE_NAME: &str = "foo";
+
+#[tokio::test(flavor = "multi_thread")]
+async fn inspect_happ() {
+    let info = match inspect_bundle_file("./fixture/test.happ", &InspectOptions::default())
+        .await
+        .unwrap()
+    {
+        BundleInfo::App(info) => info,
+        _ => panic!("Expected an app bundle"),
+    };
+
+    assert_eq!(info.roles.len(), 1);
+    let role = &info.roles[0];
+    assert_eq!(role.name, ROLE_NAME);
+    assert_eq!(role.clone_limit, 10);
+    let dna = role.dna.as_ref().unwrap();
+    assert_eq!(dna.name, "test-dna");
+    assert_eq!(dna.integrity_zomes, vec!["foo".into()]);
+
+    // The network seed changes the DNA hash
+    let bytes = std::fs::read("./fixture/test.happ").unwrap();
+    let seeded = inspect_app_bundle(
+        &bytes,
+        &InspectOptions {
+            network_seed: Some("some seed".to_string()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    assert_ne!(seeded.roles[0].dna.as_ref().unwrap().dna_hash, dna.dna_hash);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn inspect_dna_file_with_network_seed() {
+    // Take the DNA out of the test app, so that its hash can be compared with the app's role
+    let bytes = std::fs::read("./fixture/test.happ").unwrap();
+    let app_bundle = AppBundle::decode(&bytes).unwrap().into_inner();
+    let dna_bytes = app_bundle.bundled_resources().values().next().unwrap();
+    let dna_path = std::env::temp_dir().join(format!(
+        "holochain_client_inspect_{}.dna",
+        std::process::id()
+    ));
+    std::fs::write(&dna_path, dna_bytes.inner()).unwrap();
+
+    let options = InspectOptions {
+        network_seed: Some("some seed".to_string()),
+        ..Default::default()
+    };
+    let dna = match inspect_bundle_file(&dna_path, &options).await.unwrap() {
+        BundleInfo::Dna(info) => info,
+        _ => panic!("Expected a DNA bundle"),
+    };
+    let unseeded = match inspect_bundle_file(&dna_path, &InspectOptions::default())
+        .await
+        .unwrap()
+    {
+        BundleInfo::Dna(info) => info,
+        _ => panic!("Expected a DNA bundle"),
+    };
+    std::fs::remove_file(dna_path).unwrap();
+
+    assert_eq!(dna.modifiers.network_seed, Some("some seed".to_string()));
+    assert_ne!(dna.dna_hash, unseeded.dna_hash);
+    let app = inspect_app_bundle(&bytes, &options).await.unwrap();
+    assert_eq!(app.roles[0].dna.as_ref().unwrap().dna_hash, dna.dna_hash);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn inspect_does_not_read_unbundled_dnas() {
+    // Write the DNA of the test app to a file, and refer to it by its path rather than bundling it
+    let bytes = std::fs::read("./fixture/test.happ").unwrap();
+    let app_bundle = AppBundle::decode(&bytes).unwrap().into_inner();
+    let dna_bytes = app_bundle.bundled_resources().values().next().unwrap();
+    let dna_path = std::env::temp_dir().join(format!(
+        "holochain_client_inspect_unbundled_{}.dna",
+        std::process::id()
+    ));
+    std::fs::write(&dna_path, dna_bytes.inner()).unwrap();
+
+    let manifest: AppManifest = serde_yaml::from_str(&format!(
+        r#"
+manifest_version: "1"
+name: foo-test
+roles:
+  - name: foo
+    dna:
+      path: {}
+"#,
+        dna_path.display()
+    ))
+    .unwrap();
+    let bundle = AppBundle::new(
+        manifest,
+        Vec::<(PathBuf, DnaBundle)>::new(),
+        std::env::temp_dir(),
+    )
+    .await
+    .unwrap();
+    let bundle_bytes = bundle.into_inner().encode().unwrap();
+
+    let info = inspect_app_bundle(&bundle_bytes, &InspectOptions::default())
+        .await
+        .unwrap();
+    std::fs::remove_file(dna_path).unwrap();
+
+    assert_eq!(info.roles.len(), 1);
+    assert_eq!(info.roles[0].name, ROLE_NAME);
+    assert_eq!(info.roles[0].dna, None);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn inspect_unsupported_file() {
+    let err = inspect_bundle_file("./fixture/happ.yaml", &InspectOptions::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::BundleError(_)));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn predict_cell_id() {
+    let conductor = SweetConductor::from_standard_config().await;
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    let network_seed = "predicted".to_string();
+    let bytes = std::fs::read("./fixture/test.happ").unwrap();
+    let info = inspect_app_bundle(
+        &bytes,
+        &InspectOptions {
+            network_seed: Some(network_seed.clone()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    let agent_key = admin_ws.generate_agent_pub_key().await.unwrap();
+    let app_info = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: Some(agent_key.clone()),
+            installed_app_id: None,
+            network_seed: Some(network_seed),
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+
+    let cell_id = match app_info.cell_info.get(ROLE_NAME).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+    assert_eq!(info.roles[0].cell_id(&agent_key), Some(cell_id));
+}
@@ -0,0 +1,151 @@
+#![cfg(feature = "bundle_packing")]
+
+use holochain::prelude::{AppBundle, AppBundleSource, DnaBundle, DnaModifiersOpt};
+use holochain::sweettest::SweetConductor;
+use holochain_client::{
+    inspect_app_bundle, inspect_dna_bundle, pack_app_bundle, pack_dna_bundle, AdminWebsocket,
+    InspectOptions, InstallAndConnectOptions,
+};
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+use std::path::{Path, PathBuf};
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "holochain_client_bundle_packing_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Write the resources of a bundle to where its manifest expects to find them, to build without
+/// the `hc` CLI or a wasm build.
+fn write_resources<R: AsRef<[u8]>>(dir: &Path, resources: &BTreeMap<PathBuf, R>) {
+    for (path, bytes) in resources {
+        let path = dir.join(path);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, bytes).unwrap();
+    }
+}
+
+async fn fixture_app_bundle() -> AppBundle {
+    AppBundleSource::Path(PathBuf::from("./fixture/test.happ"))
+        .resolve()
+        .await
+        .unwrap()
+}
+
+async fn fixture_dna_bytes() -> Vec<u8> {
+    let app_bundle = fixture_app_bundle().await;
+    let location = app_bundle.manifest().app_roles()[0]
+        .dna
+        .location
+        .clone()
+        .unwrap();
+    app_bundle.resolve(&location).await.unwrap().to_vec()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pack_dna() {
+    let dir = test_dir("dna");
+    let dna_bytes = fixture_dna_bytes().await;
+    let dna_bundle = DnaBundle::decode(&dna_bytes).unwrap();
+    write_resources(&dir, dna_bundle.bundled_resources());
+    std::fs::copy("./fixture/dna.yaml", dir.join("dna.yaml")).unwrap();
+
+    let packed = pack_dna_bundle(dir.join("dna.yaml")).unwrap();
+
+    let expected = inspect_dna_bundle(&dna_bytes, DnaModifiersOpt::default())
+        .await
+        .unwrap();
+    let actual = inspect_dna_bundle(&packed, DnaModifiersOpt::default())
+        .await
+        .unwrap();
+    assert_eq!(actual, expected);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pack_and_install_app() {
+    let dir = test_dir("app");
+    write_resources(&dir, fixture_app_bundle().await.bundled_resources());
+    std::fs::copy("./fixture/happ.yaml", dir.join("happ.yaml")).unwrap();
+
+    let packed = pack_app_bundle(dir.join("happ.yaml")).unwrap();
+    let info = inspect_app_bundle(&packed, &InspectOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(info.roles[0].name, "foo");
+
+    let conductor = SweetConductor::from_standard_config().await;
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+    let app = admin_ws
+        .install_and_connect(
+            AppBundleSource::Bytes(packed),
+            InstallAndConnectOptions::default(),
+        )
+        .await
+        .unwrap();
+
+    let response = app
+        .app_ws
+        .call_zome(
+            "foo".to_string().into(),
+            "foo".into(),
+            "foo".into(),
+            holochain_client::ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn pack_app_with_dna_bundle_and_dna_manifest() {
+    let dir = test_dir("mixed");
+    // One role refers to a built `.dna` bundle and the other to a DNA manifest to pack
+    write_resources(&dir, fixture_app_bundle().await.bundled_resources());
+    let dna_bundle = DnaBundle::decode(&fixture_dna_bytes().await).unwrap();
+    write_resources(&dir.join("dna"), dna_bundle.bundled_resources());
+    std::fs::copy("./fixture/dna.yaml", dir.join("dna").join("dna.yml")).unwrap();
+    std::fs::write(
+        dir.join("happ.yaml"),
+        r#"
+manifest_version: "1"
+name: mixed-test
+roles:
+  - name: bundled
+    provisioning:
+      strategy: create
+    dna:
+      bundled: ./test.dna
+  - name: manifest
+    provisioning:
+      strategy: create
+    dna:
+      bundled: ./dna/dna.yml
+"#,
+    )
+    .unwrap();
+
+    let packed = pack_app_bundle(dir.join("happ.yaml")).unwrap();
+    let info = inspect_app_bundle(&packed, &InspectOptions::default())
+        .await
+        .unwrap();
+    assert_eq!(info.roles.len(), 2);
+    let bundled = info.roles[0].dna.as_ref().unwrap();
+    let manifest = info.roles[1].dna.as_ref().unwrap();
+    assert_eq!(bundled.name, "test-dna");
+    assert_eq!(manifest.dna_hash, bundled.dna_hash);
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
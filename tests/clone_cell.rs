@@ -6,14 +6,18 @@ use holochain::{
     sweettest::SweetConductor,
 };
 use holochain_client::{
-    AdminWebsocket, AppWebsocket, AuthorizeSigningCredentialsPayload, ClientAgentSigner,
-    ConductorApiError, InstallAppPayload,
+    AdminWebsocket, AgentSigner, AppWebsocket, AuthorizeSigningCredentialsPayload,
+    AutoAuthorizingAgentSigner, CellInfo, ClientAgentSigner, ConductorApiError, GrantedFunctions,
+    InstallAppPayload, RoutingAgentSigner, ZomeCallTarget,
 };
 use holochain_types::prelude::{
     AppBundleSource, CloneCellId, CloneId, CreateCloneCellPayload, DnaModifiersOpt, InstalledAppId,
 };
 use holochain_types::websocket::AllowedOrigins;
-use holochain_zome_types::{dependencies::holochain_integrity_types::ExternIO, prelude::RoleName};
+use holochain_zome_types::{
+    dependencies::holochain_integrity_types::ExternIO,
+    prelude::{CellId, RoleName},
+};
 
 #[tokio::test(flavor = "multi_thread")]
 async fn clone_cell_management() {
@@ -165,7 +169,7 @@ async fn clone_cell_management() {
     assert!(enable_clone_cell_response.is_err());
 }
 
-// Check that app info can be refreshed to allow zome calls to a clone cell identified by its clone cell id
+// Check that the cached app info is refreshed to allow zome calls to a clone cell identified by its clone cell id
 #[tokio::test(flavor = "multi_thread")]
 pub async fn app_info_refresh() {
     let conductor = SweetConductor::from_standard_config().await;
@@ -196,25 +200,32 @@ pub async fn app_info_refresh() {
 
     let signer = ClientAgentSigner::default();
 
-    // Create an app interface and connect an app agent to it
+    // Create an app interface and connect two app agents to it
     let app_api_port = admin_ws
         .attach_app_interface(0, AllowedOrigins::Any, None)
         .await
         .unwrap();
 
-    let token_issued = admin_ws
-        .issue_app_auth_token(app_id.clone().into())
+    let mut app_agent_websockets = Vec::new();
+    for _ in 0..2 {
+        let token_issued = admin_ws
+            .issue_app_auth_token(app_id.clone().into())
+            .await
+            .unwrap();
+        let app_agent_ws = AppWebsocket::connect(
+            (Ipv4Addr::LOCALHOST, app_api_port),
+            token_issued.token,
+            signer.clone().into(),
+        )
         .await
         .unwrap();
-    let mut app_agent_ws = AppWebsocket::connect(
-        (Ipv4Addr::LOCALHOST, app_api_port),
-        token_issued.token,
-        signer.clone().into(),
-    )
-    .await
-    .unwrap();
+        app_agent_websockets.push(app_agent_ws);
+    }
+    let other_app_agent_ws = app_agent_websockets.pop().unwrap();
+    let app_agent_ws = app_agent_websockets.pop().unwrap();
+    let app_agent_ws_clone = app_agent_ws.clone();
 
-    // Create a clone cell, AFTER the app agent has been created
+    // Create a clone cell, AFTER the app agents have been created
     let cloned_cell = app_agent_ws
         .create_clone_cell(CreateCloneCellPayload {
             role_name: role_name.clone(),
@@ -225,6 +236,15 @@ pub async fn app_info_refresh() {
         .await
         .unwrap();
 
+    // The cached app info is refreshed for all clones of the websocket that created the clone cell
+    assert!(app_agent_ws_clone
+        .cached_app_info()
+        .cell_info
+        .get(&role_name)
+        .unwrap()
+        .iter()
+        .any(|cell| matches!(cell, CellInfo::Cloned(c) if c.cell_id == cloned_cell.cell_id)));
+
     // Authorise signing credentials for the cloned cell
     let credentials = admin_ws
         .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
@@ -235,8 +255,9 @@ pub async fn app_info_refresh() {
         .unwrap();
     signer.add_credentials(cloned_cell.cell_id.clone(), credentials);
 
-    // Call the zome function on the clone cell, expecting a failure
-    let err = app_agent_ws
+    // The other websocket doesn't know about the clone cell yet, but refreshes its cache when the
+    // clone id isn't found, so the call succeeds
+    other_app_agent_ws
         .call_zome(
             cloned_cell.clone_id.clone().into(),
             "foo".into(),
@@ -244,23 +265,549 @@ pub async fn app_info_refresh() {
             ExternIO::encode(()).unwrap(),
         )
         .await
-        .expect_err("Should fail because the client doesn't know the clone cell exists");
+        .unwrap();
+
+    // A clone id that doesn't exist still fails after refreshing
+    let err = other_app_agent_ws
+        .call_zome(
+            CloneId::new(&role_name, 100).into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .expect_err("Should fail because the clone cell doesn't exist");
     match err {
         ConductorApiError::CellNotFound => (),
         _ => panic!("Unexpected error: {:?}", err),
     }
+}
 
-    // Refresh the app info, which means the app agent will now know about the clone cell
-    app_agent_ws.refresh_app_info().await.unwrap();
+#[tokio::test(flavor = "multi_thread")]
+pub async fn call_zome_by_dna_hash_and_clone_name() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    let app_id: InstalledAppId = "test-app".into();
+    let role_name: RoleName = "foo".into();
 
-    // Call the zome function on the clone cell again, expecting success
-    app_agent_ws
+    // Install and enable an app
+    admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let signer = ClientAgentSigner::default();
+
+    let app_api_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let token_issued = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_api_port),
+        token_issued.token,
+        signer.clone().into(),
+    )
+    .await
+    .unwrap();
+
+    // Create two clone cells with the same name
+    let mut cloned_cells = Vec::new();
+    for seed in ["seed 1", "seed 2"] {
+        let cloned_cell = app_ws
+            .create_clone_cell(CreateCloneCellPayload {
+                role_name: role_name.clone(),
+                modifiers: DnaModifiersOpt::none().with_network_seed(seed.into()),
+                membrane_proof: None,
+                name: Some("shared name".to_string()),
+            })
+            .await
+            .unwrap();
+        let credentials = admin_ws
+            .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+                cell_id: cloned_cell.cell_id.clone(),
+                functions: None,
+            })
+            .await
+            .unwrap();
+        signer.add_credentials(cloned_cell.cell_id.clone(), credentials);
+        cloned_cells.push(cloned_cell);
+    }
+
+    // Calling by DNA hash finds the clone cell
+    let response = app_ws
         .call_zome(
-            cloned_cell.clone_id.clone().into(),
+            cloned_cells[0].cell_id.dna_hash().clone().into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+
+    // Calling by a clone name that is shared between clones is ambiguous
+    let err = app_ws
+        .call_zome(
+            ZomeCallTarget::CloneName("shared name".to_string()),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .expect_err("Should fail because two clone cells have the same name");
+    match err {
+        ConductorApiError::AmbiguousCellTarget(cell_ids) => assert_eq!(2, cell_ids.len()),
+        _ => panic!("Unexpected error: {:?}", err),
+    }
+
+    // Calling by an unknown clone name fails
+    let err = app_ws
+        .call_zome(
+            ZomeCallTarget::CloneName("unknown".to_string()),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .expect_err("Should fail because no clone cell has this name");
+    match err {
+        ConductorApiError::CellNotFound => (),
+        _ => panic!("Unexpected error: {:?}", err),
+    }
+
+    // Disabled clone cells are not matched by their name or DNA hash
+    app_ws
+        .disable_clone_cell(DisableCloneCellPayload {
+            clone_cell_id: CloneCellId::CloneId(cloned_cells[0].clone_id.clone()),
+        })
+        .await
+        .unwrap();
+    let response = app_ws
+        .call_zome(
+            ZomeCallTarget::CloneName("shared name".to_string()),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+    let err = app_ws
+        .call_zome(
+            cloned_cells[0].cell_id.dna_hash().clone().into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .expect_err("Should fail because the clone cell with this DNA hash is disabled");
+    match err {
+        ConductorApiError::CellNotFound => (),
+        _ => panic!("Unexpected error: {:?}", err),
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn routing_signer() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    let app_id: InstalledAppId = "test-app".into();
+    let role_name: RoleName = "foo".into();
+
+    // Install and enable an app
+    let app_info = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let provisioned_cell_id = match app_info.cell_info.get(&role_name).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    // Route the provisioned cell to one signer and everything else to another
+    let provisioned_signer = ClientAgentSigner::default();
+    let fallback_signer = ClientAgentSigner::default();
+    let signer = RoutingAgentSigner::new();
+    signer.route_cell(
+        provisioned_cell_id.clone(),
+        provisioned_signer.clone().into(),
+    );
+    signer.set_fallback(fallback_signer.clone().into());
+
+    let app_api_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let token_issued = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_api_port),
+        token_issued.token,
+        signer.clone().into(),
+    )
+    .await
+    .unwrap();
+
+    let cloned_cell = app_ws
+        .create_clone_cell(CreateCloneCellPayload {
+            role_name: role_name.clone(),
+            modifiers: DnaModifiersOpt::none().with_network_seed("test seed".into()),
+            membrane_proof: None,
+            name: None,
+        })
+        .await
+        .unwrap();
+
+    for (cell_id, cell_signer) in [
+        (provisioned_cell_id.clone(), &provisioned_signer),
+        (cloned_cell.cell_id.clone(), &fallback_signer),
+    ] {
+        let credentials = admin_ws
+            .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+                cell_id: cell_id.clone(),
+                functions: None,
+            })
+            .await
+            .unwrap();
+        cell_signer.add_credentials(cell_id, credentials);
+    }
+
+    // Each cell is signed for by its own signer
+    assert_eq!(
+        signer.get_provenance(&provisioned_cell_id),
+        provisioned_signer.get_provenance(&provisioned_cell_id)
+    );
+    assert_eq!(
+        signer.get_provenance(&cloned_cell.cell_id),
+        fallback_signer.get_provenance(&cloned_cell.cell_id)
+    );
+
+    for cell_id in [provisioned_cell_id, cloned_cell.cell_id] {
+        let response = app_ws
+            .call_zome(
+                cell_id.into(),
+                "foo".into(),
+                "foo".into(),
+                ExternIO::encode(()).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.decode::<String>().unwrap(), "foo");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn routing_signer_precedence() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    let app_id: InstalledAppId = "test-app".into();
+    let role_name: RoleName = "foo".into();
+
+    // Install and enable an app
+    let app_info = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let provisioned_cell_id = match app_info.cell_info.get(&role_name).unwrap()[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    let signer = RoutingAgentSigner::new();
+    let app_api_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let token_issued = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_api_port),
+        token_issued.token,
+        signer.clone().into(),
+    )
+    .await
+    .unwrap();
+
+    // The clone cell has the same role as the provisioned cell, but a different DNA hash
+    let cloned_cell = app_ws
+        .create_clone_cell(CreateCloneCellPayload {
+            role_name: role_name.clone(),
+            modifiers: DnaModifiersOpt::none().with_network_seed("test seed".into()),
+            membrane_proof: None,
+            name: None,
+        })
+        .await
+        .unwrap();
+    let cell_ids = [provisioned_cell_id.clone(), cloned_cell.cell_id.clone()];
+
+    // Every signer holds its own credentials for both cells, so the provenance shows which
+    // signer a cell was routed to
+    let cell_signer = ClientAgentSigner::default();
+    let role_signer = ClientAgentSigner::default();
+    let dna_signer = ClientAgentSigner::default();
+    let fallback_signer = ClientAgentSigner::default();
+    for client_signer in [&cell_signer, &role_signer, &dna_signer, &fallback_signer] {
+        for cell_id in &cell_ids {
+            let credentials = admin_ws
+                .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+                    cell_id: cell_id.clone(),
+                    functions: None,
+                })
+                .await
+                .unwrap();
+            client_signer.add_credentials(cell_id.clone(), credentials);
+        }
+    }
+    let routed_to = |cell_id: &CellId, client_signer: &ClientAgentSigner| {
+        let provenance = signer.get_provenance(cell_id);
+        assert!(provenance.is_some());
+        provenance == client_signer.get_provenance(cell_id)
+    };
+
+    // Nothing is routed yet
+    assert_eq!(signer.get_provenance(&provisioned_cell_id), None);
+
+    // Cells that match no other route use the fallback
+    signer.set_fallback(fallback_signer.clone().into());
+    assert!(routed_to(&provisioned_cell_id, &fallback_signer));
+    assert!(routed_to(&cloned_cell.cell_id, &fallback_signer));
+
+    // A DNA route takes precedence over the fallback, and only matches cells with that DNA hash
+    signer.route_dna(
+        provisioned_cell_id.dna_hash().clone(),
+        dna_signer.clone().into(),
+    );
+    assert!(routed_to(&provisioned_cell_id, &dna_signer));
+    assert!(routed_to(&cloned_cell.cell_id, &fallback_signer));
+
+    // A role route takes precedence over a DNA route, and includes clone cells of the role. The
+    // websocket keeps the roles of the cells up to date, so the role route matches without setting
+    // the app info
+    signer.route_role(role_name.clone(), role_signer.clone().into());
+    assert!(routed_to(&provisioned_cell_id, &role_signer));
+    assert!(routed_to(&cloned_cell.cell_id, &role_signer));
+
+    // Clone cells created later are routed by their role too
+    let later_cell = app_ws
+        .create_clone_cell(CreateCloneCellPayload {
+            role_name: role_name.clone(),
+            modifiers: DnaModifiersOpt::none().with_network_seed("later seed".into()),
+            membrane_proof: None,
+            name: None,
+        })
+        .await
+        .unwrap();
+    let credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: later_cell.cell_id.clone(),
+            functions: None,
+        })
+        .await
+        .unwrap();
+    role_signer.add_credentials(later_cell.cell_id.clone(), credentials);
+    assert!(routed_to(&later_cell.cell_id, &role_signer));
+
+    // A signer without a websocket only knows the roles it is given
+    let unattached = RoutingAgentSigner::new();
+    unattached.route_role(role_name.clone(), role_signer.clone().into());
+    assert_eq!(unattached.get_provenance(&provisioned_cell_id), None);
+    unattached.set_app_info(&app_ws.cached_app_info());
+    assert!(unattached.get_provenance(&provisioned_cell_id).is_some());
+
+    // A cell route takes precedence over all others
+    signer.route_cell(cloned_cell.cell_id.clone(), cell_signer.clone().into());
+    assert!(routed_to(&provisioned_cell_id, &role_signer));
+    assert!(routed_to(&cloned_cell.cell_id, &cell_signer));
+
+    // Zome calls are signed by the routed signers
+    for cell_id in cell_ids {
+        let response = app_ws
+            .call_zome(
+                cell_id.into(),
+                "foo".into(),
+                "foo".into(),
+                ExternIO::encode(()).unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.decode::<String>().unwrap(), "foo");
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+pub async fn auto_authorizing_signer() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    let app_id: InstalledAppId = "test-app".into();
+    let role_name: RoleName = "foo".into();
+
+    // Install and enable an app
+    admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    // Connect with a signer that has no credentials yet
+    let signer = AutoAuthorizingAgentSigner::new(admin_ws.clone(), None);
+    let app_api_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let token_issued = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_api_port),
+        token_issued.token,
+        signer.clone().into(),
+    )
+    .await
+    .unwrap();
+
+    // The provisioned cell is authorized on the first call
+    let response = app_ws
+        .call_zome(
+            role_name.clone().into(),
             "foo".into(),
             "foo".into(),
             ExternIO::encode(()).unwrap(),
         )
         .await
         .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+
+    // A clone cell created later is also authorized on the first call
+    let cloned_cell = app_ws
+        .create_clone_cell(CreateCloneCellPayload {
+            role_name: role_name.clone(),
+            modifiers: DnaModifiersOpt::none().with_network_seed("test seed".into()),
+            membrane_proof: None,
+            name: None,
+        })
+        .await
+        .unwrap();
+    assert!(!signer.client_signer().has_credentials(&cloned_cell.cell_id));
+
+    let response = app_ws
+        .call_zome(
+            cloned_cell.clone_id.into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+    assert!(signer.client_signer().has_credentials(&cloned_cell.cell_id));
+
+    // Existing credentials which don't cover the function being called are not enough
+    let scoped_signer = ClientAgentSigner::default();
+    let credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: cloned_cell.cell_id.clone(),
+            functions: Some(GrantedFunctions::Listed(
+                [("foo".into(), "foo".into())].into_iter().collect(),
+            )),
+        })
+        .await
+        .unwrap();
+    let scoped_key = credentials.signing_agent_key.clone();
+    scoped_signer.add_scoped_credentials(
+        cloned_cell.cell_id.clone(),
+        GrantedFunctions::Listed([("foo".into(), "foo".into())].into_iter().collect()),
+        credentials,
+    );
+    let signer = AutoAuthorizingAgentSigner::with_signer(admin_ws.clone(), None, scoped_signer);
+
+    signer
+        .prepare_for_fn(&cloned_cell.cell_id, &"foo".into(), &"foo".into())
+        .await
+        .unwrap();
+    assert!(signer
+        .get_provenance_for_fn(&cloned_cell.cell_id, &"foo".into(), &"bar".into())
+        .is_none());
+
+    signer
+        .prepare_for_fn(&cloned_cell.cell_id, &"foo".into(), &"bar".into())
+        .await
+        .unwrap();
+    let all_key = signer
+        .get_provenance_for_fn(&cloned_cell.cell_id, &"foo".into(), &"bar".into())
+        .unwrap();
+    assert_ne!(all_key, scoped_key);
+    assert_eq!(
+        signer.get_provenance_for_fn(&cloned_cell.cell_id, &"foo".into(), &"foo".into()),
+        Some(scoped_key)
+    );
 }
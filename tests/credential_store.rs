@@ -0,0 +1,83 @@
+#![cfg(feature = "credential_store")]
+
+use holochain::prelude::{AgentPubKey, CapSecret, DnaHash};
+use holochain_client::{
+    AgentSigner, CellId, ClientAgentSigner, ConductorApiError, CredentialStore, SigningCredentials,
+};
+use std::path::PathBuf;
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "holochain_client_credential_store_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn test_cell_id(seed: u8) -> CellId {
+    CellId::new(
+        DnaHash::from_raw_32(vec![seed; 32]),
+        AgentPubKey::from_raw_32(vec![seed; 32]),
+    )
+}
+
+fn test_credentials() -> SigningCredentials {
+    let keypair = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+    SigningCredentials {
+        signing_agent_key: AgentPubKey::from_raw_32(keypair.verifying_key().as_bytes().to_vec()),
+        keypair,
+        cap_secret: CapSecret::from([7; 64]),
+    }
+}
+
+#[test]
+fn save_and_load_credentials() {
+    let dir = test_dir("save_and_load");
+    let cell_id = test_cell_id(1);
+    let credentials = test_credentials();
+
+    let store = CredentialStore::open(&dir, "passphrase").unwrap();
+    assert!(store.load(&cell_id).unwrap().is_none());
+    store.save(&cell_id, &credentials).unwrap();
+
+    // Load from a new store, as a restarted client would
+    let store = CredentialStore::open(&dir, "passphrase").unwrap();
+    let loaded = store.load(&cell_id).unwrap().unwrap();
+    assert_eq!(loaded.signing_agent_key, credentials.signing_agent_key);
+    assert_eq!(loaded.keypair.to_bytes(), credentials.keypair.to_bytes());
+    assert_eq!(loaded.cap_secret, credentials.cap_secret);
+
+    let signer = ClientAgentSigner::default();
+    let loaded_cell_ids = store.load_all_into(&signer).unwrap();
+    assert_eq!(loaded_cell_ids, vec![cell_id.clone()]);
+    assert_eq!(
+        signer.get_provenance(&cell_id),
+        Some(credentials.signing_agent_key.clone())
+    );
+
+    store.remove(&cell_id).unwrap();
+    assert!(store.load(&cell_id).unwrap().is_none());
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn wrong_passphrase_is_rejected() {
+    let dir = test_dir("wrong_passphrase");
+    let cell_id = test_cell_id(2);
+
+    CredentialStore::open(&dir, "passphrase")
+        .unwrap()
+        .save(&cell_id, &test_credentials())
+        .unwrap();
+
+    let err = CredentialStore::open(&dir, "not the passphrase")
+        .unwrap()
+        .load(&cell_id)
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::CredentialStoreError(_)));
+
+    std::fs::remove_dir_all(dir).unwrap();
+}
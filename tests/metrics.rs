@@ -0,0 +1,113 @@
+#![cfg(feature = "metrics")]
+
+use holochain::{prelude::AppBundleSource, sweettest::SweetConductor};
+use holochain_client::{
+    set_metrics_recorder, AdminWebsocket, AppWebsocket, AuthorizeSigningCredentialsPayload,
+    ClientAgentSigner, InstallAppPayload, InstalledAppId, PrometheusRecorder,
+};
+use holochain_conductor_api::CellInfo;
+use holochain_types::websocket::AllowedOrigins;
+use holochain_zome_types::prelude::ExternIO;
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn records_request_metrics() {
+    let recorder = Arc::new(PrometheusRecorder::default());
+    set_metrics_recorder(recorder.clone());
+
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    // Connect app agent client
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let issued_token = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let signer = ClientAgentSigner::default();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        issued_token.token,
+        signer.clone().into(),
+    )
+    .await
+    .unwrap();
+
+    let cells = installed_app.cell_info.into_values().next().unwrap();
+    let cell_id = match cells[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    let credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: cell_id.clone(),
+            functions: None,
+        })
+        .await
+        .unwrap();
+    signer.add_credentials(cell_id.clone(), credentials);
+
+    app_ws
+        .call_zome(
+            cell_id.clone().into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    app_ws
+        .call_zome(
+            cell_id.into(),
+            "foo".into(),
+            "not_a_function".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap_err();
+
+    let rendered = recorder.render();
+
+    assert!(rendered.contains(
+        "holochain_client_requests_total{interface=\"admin\",request=\"install_app\"} 1"
+    ));
+    assert!(rendered.contains(
+        "holochain_client_requests_total{interface=\"app\",request=\"call_zome\",zome=\"foo\",fn_name=\"foo\"} 1"
+    ));
+    assert!(rendered.contains(
+        "holochain_client_request_errors_total{interface=\"app\",request=\"call_zome\",zome=\"foo\",fn_name=\"foo\"} 0"
+    ));
+    assert!(rendered.contains(
+        "holochain_client_request_errors_total{interface=\"app\",request=\"call_zome\",zome=\"foo\",fn_name=\"not_a_function\"} 1"
+    ));
+    assert!(rendered.contains(
+        "holochain_client_requests_in_flight{interface=\"app\",request=\"call_zome\",zome=\"foo\",fn_name=\"foo\"} 0"
+    ));
+}
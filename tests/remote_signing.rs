@@ -0,0 +1,136 @@
+#![cfg(feature = "remote_signing")]
+
+use holochain::{
+    prelude::{AppBundleSource, FunctionName, ZomeName},
+    sweettest::SweetConductor,
+};
+use holochain_client::{
+    AdminWebsocket, AgentSigner, AppWebsocket, AuthorizeSigningCredentialsPayload, CellInfo,
+    ClientAgentSigner, ConductorApiError, ExternIO, GrantedFunctions, InstallAppPayload,
+    InstalledAppId, RemoteAgentSigner, RemoteSignerDaemon, RemoteSignerEndpoint, RemoteSignerKey,
+};
+use holochain_types::websocket::AllowedOrigins;
+use std::{net::Ipv4Addr, path::PathBuf};
+
+#[tokio::test(flavor = "multi_thread")]
+async fn remote_signer() {
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    let installed_app = admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    let cells = installed_app.cell_info.into_values().next().unwrap();
+    let cell_id = match cells[0].clone() {
+        CellInfo::Provisioned(c) => c.cell_id,
+        _ => panic!("Invalid cell type"),
+    };
+
+    // The daemon holds the credentials
+    let daemon_signer = ClientAgentSigner::default();
+    let credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: cell_id.clone(),
+            functions: None,
+        })
+        .await
+        .unwrap();
+    daemon_signer.add_credentials(cell_id.clone(), credentials);
+
+    let key = RemoteSignerKey::generate();
+    let listener = tokio::net::TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .unwrap();
+    let endpoint = RemoteSignerEndpoint::Tcp(listener.local_addr().unwrap());
+    tokio::spawn(
+        RemoteSignerDaemon::new(daemon_signer.clone().into(), key.clone()).serve_tcp(listener),
+    );
+
+    // A client with the wrong key is refused
+    let err = RemoteAgentSigner::connect(endpoint.clone(), RemoteSignerKey::generate())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::RemoteSignerError(_)));
+
+    let signer = RemoteAgentSigner::connect(endpoint, key).await.unwrap();
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let token_issued = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        token_issued.token,
+        signer.clone().into(),
+    )
+    .await
+    .unwrap();
+
+    let response = app_ws
+        .call_zome(
+            cell_id.clone().into(),
+            "foo".into(),
+            "foo".into(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "foo");
+
+    // Credentials that the daemon holds for a single function are used for calls to it
+    let bar: (ZomeName, FunctionName) = ("foo".into(), "bar".into());
+    let scoped_credentials = admin_ws
+        .authorize_signing_credentials(AuthorizeSigningCredentialsPayload {
+            cell_id: cell_id.clone(),
+            functions: Some(GrantedFunctions::Listed([bar.clone()].into())),
+        })
+        .await
+        .unwrap();
+    let scoped_provenance = scoped_credentials.signing_agent_key.clone();
+    daemon_signer.add_scoped_credentials(
+        cell_id.clone(),
+        GrantedFunctions::Listed([bar.clone()].into()),
+        scoped_credentials,
+    );
+
+    let response = app_ws
+        .call_zome(
+            cell_id.clone().into(),
+            bar.0.clone(),
+            bar.1.clone(),
+            ExternIO::encode(()).unwrap(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(response.decode::<String>().unwrap(), "bar");
+    assert_eq!(
+        signer.get_provenance_for_fn(&cell_id, &bar.0, &bar.1),
+        Some(scoped_provenance)
+    );
+    assert_eq!(
+        signer.get_provenance_for_fn(&cell_id, &"foo".into(), &"foo".into()),
+        daemon_signer.get_provenance(&cell_id)
+    );
+}
@@ -0,0 +1,277 @@
+use holochain::prelude::{CellId, DnaHash};
+use holochain_client::{
+    read_audit_log, verify_audit_log, AgentPubKey, AgentSigner, AuditLogKey, AuditingAgentSigner,
+    ClientAgentSigner, ConductorApiError, DynAgentSigner, ExternIO, PolicyAgentSigner,
+    PolicyViolation, SignedZomeCall, SigningCredentials, SigningPolicy, Timestamp, ZomeCallParams,
+    ZomeCallVerificationError,
+};
+use holochain_zome_types::capability::CapSecret;
+use std::time::Duration;
+
+fn test_signer(cell_id: &CellId) -> ClientAgentSigner {
+    let keypair = ed25519_dalek::SigningKey::generate(&mut rand::rngs::OsRng);
+    let signer = ClientAgentSigner::default();
+    signer.add_credentials(
+        cell_id.clone(),
+        SigningCredentials {
+            signing_agent_key: AgentPubKey::from_raw_32(
+                keypair.verifying_key().as_bytes().to_vec(),
+            ),
+            keypair,
+            cap_secret: CapSecret::from([1; 64]),
+        },
+    );
+    signer
+}
+
+async fn sign(
+    signer: &ClientAgentSigner,
+    cell_id: &CellId,
+    expires_at: Timestamp,
+) -> SignedZomeCall {
+    let params = ZomeCallParams {
+        provenance: signer.get_provenance(cell_id).unwrap(),
+        cap_secret: signer.get_cap_secret(cell_id),
+        cell_id: cell_id.clone(),
+        zome_name: "foo".into(),
+        fn_name: "foo".into(),
+        payload: ExternIO::encode(()).unwrap(),
+        expires_at,
+        nonce: holochain_nonce::fresh_nonce(Timestamp::now()).unwrap().0,
+    };
+    SignedZomeCall::sign(params, signer.clone().into())
+        .await
+        .unwrap()
+}
+
+fn in_future(duration: Duration) -> Timestamp {
+    Timestamp::from_micros(Timestamp::now().as_micros() + duration.as_micros() as i64)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn verify_signed_zome_call() {
+    let cell_id = CellId::new(
+        DnaHash::from_raw_32(vec![1; 32]),
+        AgentPubKey::from_raw_32(vec![1; 32]),
+    );
+    let signer = test_signer(&cell_id);
+
+    let signed = sign(&signer, &cell_id, in_future(Duration::from_secs(60))).await;
+    let params = holochain_client::verify_zome_call(
+        &signed.clone().into_signed_params(),
+        Duration::from_secs(5),
+    )
+    .unwrap();
+    assert_eq!(params.cell_id, cell_id);
+    assert_eq!(params.provenance, signer.get_provenance(&cell_id).unwrap());
+
+    // Changing the signed bytes invalidates the signature
+    let mut tampered = signed.into_signed_params();
+    tampered.signature.0[0] ^= 1;
+    let err = holochain_client::verify_zome_call(&tampered, Duration::from_secs(5)).unwrap_err();
+    assert!(matches!(
+        err,
+        ConductorApiError::ZomeCallVerificationFailed(ZomeCallVerificationError::InvalidSignature)
+    ));
+
+    // A call that has just expired is accepted within the clock skew
+    let expired = sign(
+        &signer,
+        &cell_id,
+        Timestamp::from_micros(Timestamp::now().as_micros() - 1_000_000),
+    )
+    .await;
+    expired.verify(Duration::from_secs(5)).unwrap();
+    let err = expired.verify(Duration::ZERO).unwrap_err();
+    assert!(matches!(
+        err,
+        ConductorApiError::ZomeCallVerificationFailed(ZomeCallVerificationError::Expired(_))
+    ));
+
+    // A call that expires later than a fresh nonce would is rejected
+    let late = sign(&signer, &cell_id, in_future(Duration::from_secs(3600))).await;
+    let err = late.verify(Duration::from_secs(5)).unwrap_err();
+    assert!(matches!(
+        err,
+        ConductorApiError::ZomeCallVerificationFailed(ZomeCallVerificationError::ExpiresTooLate(_))
+    ));
+
+    // Expiries at the limits of the timestamp range don't overflow, even with the largest skew
+    let latest = sign(&signer, &cell_id, Timestamp::from_micros(i64::MAX)).await;
+    let err = latest.verify(Duration::from_secs(5)).unwrap_err();
+    assert!(matches!(
+        err,
+        ConductorApiError::ZomeCallVerificationFailed(ZomeCallVerificationError::ExpiresTooLate(_))
+    ));
+    latest.verify(Duration::MAX).unwrap();
+
+    let earliest = sign(&signer, &cell_id, Timestamp::from_micros(i64::MIN)).await;
+    for max_clock_skew in [Duration::from_secs(5), Duration::MAX] {
+        let err = earliest.verify(max_clock_skew).unwrap_err();
+        assert!(matches!(
+            err,
+            ConductorApiError::ZomeCallVerificationFailed(ZomeCallVerificationError::Expired(_))
+        ));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn signing_policy_expiry_limits() {
+    let cell_id = CellId::new(
+        DnaHash::from_raw_32(vec![3; 32]),
+        AgentPubKey::from_raw_32(vec![3; 32]),
+    );
+    let client_signer = test_signer(&cell_id);
+    let signer: DynAgentSigner = PolicyAgentSigner::new(
+        client_signer.clone().into(),
+        SigningPolicy {
+            max_expires_in: Some(Duration::from_secs(60)),
+            ..Default::default()
+        },
+    )
+    .into();
+
+    let params = |expires_at| ZomeCallParams {
+        provenance: client_signer.get_provenance(&cell_id).unwrap(),
+        cap_secret: client_signer.get_cap_secret(&cell_id),
+        cell_id: cell_id.clone(),
+        zome_name: "foo".into(),
+        fn_name: "foo".into(),
+        payload: ExternIO::encode(()).unwrap(),
+        expires_at,
+        nonce: holochain_nonce::fresh_nonce(Timestamp::now()).unwrap().0,
+    };
+
+    // The latest expiry doesn't wrap around to pass the check
+    let err = SignedZomeCall::sign(params(Timestamp::from_micros(i64::MAX)), signer.clone())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ConductorApiError::SigningPolicyViolation(PolicyViolation::ExpiryTooLate)
+    ));
+
+    // The earliest expiry doesn't overflow, and is left for the conductor to reject
+    SignedZomeCall::sign(params(Timestamp::from_micros(i64::MIN)), signer.clone())
+        .await
+        .unwrap();
+
+    // The largest limit allows any expiry
+    let signer: DynAgentSigner = PolicyAgentSigner::new(
+        client_signer.clone().into(),
+        SigningPolicy {
+            max_expires_in: Some(Duration::MAX),
+            ..Default::default()
+        },
+    )
+    .into();
+    SignedZomeCall::sign(params(Timestamp::from_micros(i64::MAX)), signer)
+        .await
+        .unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn audit_log() {
+    let path =
+        std::env::temp_dir().join(format!("holochain_client_audit_log_{}", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    let cell_id = CellId::new(
+        DnaHash::from_raw_32(vec![2; 32]),
+        AgentPubKey::from_raw_32(vec![2; 32]),
+    );
+    let client_signer = test_signer(&cell_id);
+
+    let key = AuditLogKey::generate();
+    let signer =
+        AuditingAgentSigner::open(&path, key.clone(), client_signer.clone().into()).unwrap();
+    for _ in 0..2 {
+        sign_with(&client_signer, signer.clone().into(), &cell_id).await;
+    }
+
+    // Only one signer can write to a log at a time
+    let err =
+        AuditingAgentSigner::open(&path, key.clone(), client_signer.clone().into()).unwrap_err();
+    assert!(matches!(err, ConductorApiError::AuditLogError(_)));
+    drop(signer);
+
+    // Entries are appended to an existing log
+    let signer =
+        AuditingAgentSigner::open(&path, key.clone(), client_signer.clone().into()).unwrap();
+    let signed = sign_with(&client_signer, signer.clone().into(), &cell_id).await;
+
+    // A call whose entry would be too large to read back is refused, and the log stays readable
+    let params = ZomeCallParams {
+        provenance: client_signer.get_provenance(&cell_id).unwrap(),
+        cap_secret: client_signer.get_cap_secret(&cell_id),
+        cell_id: cell_id.clone(),
+        zome_name: "foo".into(),
+        fn_name: "f".repeat(100 * 1024).into(),
+        payload: ExternIO::encode(()).unwrap(),
+        expires_at: in_future(Duration::from_secs(60)),
+        nonce: holochain_nonce::fresh_nonce(Timestamp::now()).unwrap().0,
+    };
+    assert!(SignedZomeCall::sign(params, signer.into()).await.is_err());
+
+    let summary = verify_audit_log(&path, &key).unwrap();
+    assert_eq!(summary.entries, 3);
+    let entries = read_audit_log(&path).unwrap();
+    assert_eq!(entries[2].hash, summary.head_hash);
+    assert_eq!(entries[2].zome_name, "foo".into());
+    assert_eq!(entries[2].signature, signed.into_signed_params().signature);
+
+    // The log only verifies with the key it was written with
+    let err = verify_audit_log(&path, &AuditLogKey::generate()).unwrap_err();
+    assert!(matches!(err, ConductorApiError::AuditLogError(_)));
+
+    // An entry that was only partly written is removed when the log is opened
+    let complete = std::fs::read(&path).unwrap();
+    let mut bytes = complete.clone();
+    bytes.extend_from_slice(&100u32.to_be_bytes());
+    bytes.extend_from_slice(&[0; 10]);
+    std::fs::write(&path, &bytes).unwrap();
+    let err = read_audit_log(&path).unwrap_err();
+    assert!(matches!(err, ConductorApiError::AuditLogError(_)));
+    let signer =
+        AuditingAgentSigner::open(&path, key.clone(), client_signer.clone().into()).unwrap();
+    assert_eq!(std::fs::read(&path).unwrap(), complete);
+    sign_with(&client_signer, signer.into(), &cell_id).await;
+    assert_eq!(verify_audit_log(&path, &key).unwrap().entries, 4);
+
+    // A frame length larger than any entry is rejected without allocating it
+    let mut bytes = std::fs::read(&path).unwrap();
+    bytes.extend_from_slice(&u32::MAX.to_be_bytes());
+    std::fs::write(&path, &bytes).unwrap();
+    let err = read_audit_log(&path).unwrap_err();
+    assert!(matches!(err, ConductorApiError::AuditLogError(_)));
+    std::fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+
+    // Modifying an entry is detected
+    let mut bytes = std::fs::read(&path).unwrap();
+    let fn_name_at = bytes.windows(3).rposition(|w| w == b"foo").unwrap();
+    bytes[fn_name_at] = b'g';
+    std::fs::write(&path, &bytes).unwrap();
+    let err = verify_audit_log(&path, &key).unwrap_err();
+    assert!(matches!(err, ConductorApiError::AuditLogError(_)));
+    assert!(AuditingAgentSigner::open(&path, key, client_signer.into()).is_err());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+async fn sign_with(
+    client_signer: &ClientAgentSigner,
+    signer: DynAgentSigner,
+    cell_id: &CellId,
+) -> SignedZomeCall {
+    let params = ZomeCallParams {
+        provenance: client_signer.get_provenance(cell_id).unwrap(),
+        cap_secret: client_signer.get_cap_secret(cell_id),
+        cell_id: cell_id.clone(),
+        zome_name: "foo".into(),
+        fn_name: "foo".into(),
+        payload: ExternIO::encode(()).unwrap(),
+        expires_at: in_future(Duration::from_secs(60)),
+        nonce: holochain_nonce::fresh_nonce(Timestamp::now()).unwrap().0,
+    };
+    SignedZomeCall::sign(params, signer).await.unwrap()
+}
@@ -0,0 +1,218 @@
+#![cfg(feature = "tracing")]
+
+use holochain::{prelude::EnableCloneCellPayload, sweettest::SweetConductor};
+use holochain_client::{
+    AdminWebsocket, AllowedOrigins, AppBundleSource, AppWebsocket, ClientAgentSigner,
+    ConductorApiError, InstallAppPayload, InstalledAppId,
+};
+use holochain_types::prelude::{CloneCellId, CloneId};
+use std::{
+    net::Ipv4Addr,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+use tracing::{
+    field::{Field, Visit},
+    span, Event, Subscriber,
+};
+use tracing_subscriber::{
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    Layer,
+};
+
+/// A span or event recorded by the client, with the fields recorded on it.
+#[derive(Debug, Clone)]
+struct Captured {
+    name: String,
+    fields: Vec<(String, String)>,
+}
+
+impl Captured {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl Visit for Captured {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields
+            .push((field.name().to_string(), value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.fields
+            .push((field.name().to_string(), format!("{value:?}")));
+    }
+}
+
+/// Captures the spans of the client, and the events in them, ignoring those of the conductor.
+#[derive(Clone, Default)]
+struct CapturingLayer {
+    spans: Arc<Mutex<Vec<(span::Id, Captured)>>>,
+    /// Events, with the client span they were recorded in.
+    events: Arc<Mutex<Vec<(Captured, Option<Captured>)>>>,
+}
+
+impl CapturingLayer {
+    /// The `request` field of every `send` span.
+    fn requests(&self) -> Vec<String> {
+        self.spans
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, span)| span.name == "send")
+            .filter_map(|(_, span)| span.field("request").map(str::to_string))
+            .collect()
+    }
+
+    /// The `request` field of every `send` span that an error was recorded in.
+    fn failed_requests(&self) -> Vec<String> {
+        self.events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(event, _)| event.field("error").is_some())
+            .filter_map(|(_, span)| span.as_ref())
+            .filter(|span| span.name == "send")
+            .filter_map(|span| span.field("request").map(str::to_string))
+            .collect()
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for CapturingLayer {
+    fn on_new_span(&self, attrs: &span::Attributes<'_>, id: &span::Id, _ctx: Context<'_, S>) {
+        if !attrs.metadata().target().starts_with("holochain_client") {
+            return;
+        }
+        let mut span = Captured {
+            name: attrs.metadata().name().to_string(),
+            fields: Vec::new(),
+        };
+        attrs.record(&mut span);
+        self.spans.lock().unwrap().push((id.clone(), span));
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record<'_>, _ctx: Context<'_, S>) {
+        if let Some((_, span)) = self
+            .spans
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .rev()
+            .find(|(span_id, _)| span_id == id)
+        {
+            values.record(span);
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if !event.metadata().target().starts_with("holochain_client") {
+            return;
+        }
+        let mut captured = Captured {
+            name: event.metadata().name().to_string(),
+            fields: Vec::new(),
+        };
+        event.record(&mut captured);
+        let span = ctx.event_span(event).and_then(|span| {
+            self.spans
+                .lock()
+                .unwrap()
+                .iter()
+                .rev()
+                .find(|(span_id, _)| *span_id == span.id())
+                .map(|(_, span)| span.clone())
+        });
+        self.events.lock().unwrap().push((captured, span));
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn request_spans_record_kind_and_errors() {
+    // Spans are created on the runtime's worker threads, so the subscriber must be global. This is
+    // the only test in this file, so it is only set once.
+    let layer = CapturingLayer::default();
+    tracing::subscriber::set_global_default(tracing_subscriber::registry().with(layer.clone()))
+        .unwrap();
+
+    let conductor = SweetConductor::from_standard_config().await;
+
+    // Connect admin client
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    let admin_ws = AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap();
+
+    // Set up the test app
+    let app_id: InstalledAppId = "test-app".into();
+    admin_ws
+        .install_app(InstallAppPayload {
+            agent_key: None,
+            installed_app_id: Some(app_id.clone()),
+            network_seed: None,
+            roles_settings: None,
+            source: AppBundleSource::Path(PathBuf::from("./fixture/test.happ")),
+            ignore_genesis_failure: false,
+            allow_throwaway_random_agent_key: false,
+        })
+        .await
+        .unwrap();
+    admin_ws.enable_app(app_id.clone()).await.unwrap();
+
+    // Admin requests that fail record the error in their span
+    let err = admin_ws
+        .enable_app("not-an-app".to_string())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::ExternalApiWireError(_)));
+
+    let app_ws_port = admin_ws
+        .attach_app_interface(0, AllowedOrigins::Any, None)
+        .await
+        .unwrap();
+    let token_issued = admin_ws
+        .issue_app_auth_token(app_id.clone().into())
+        .await
+        .unwrap();
+    let app_ws = AppWebsocket::connect(
+        (Ipv4Addr::LOCALHOST, app_ws_port),
+        token_issued.token,
+        ClientAgentSigner::default().into(),
+    )
+    .await
+    .unwrap();
+
+    // App requests that fail record the error in their span too
+    let err = app_ws
+        .enable_clone_cell(EnableCloneCellPayload {
+            clone_cell_id: CloneCellId::CloneId(CloneId::new(&"foo".into(), 99)),
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::ExternalApiWireError(_)));
+
+    let requests = layer.requests();
+    for request in [
+        "install_app",
+        "enable_app",
+        "attach_app_interface",
+        "issue_app_authentication_token",
+        "app_info",
+        "enable_clone_cell",
+    ] {
+        assert!(
+            requests.iter().any(|r| r == request),
+            "No span for {request} in {requests:?}"
+        );
+    }
+
+    let failed_requests = layer.failed_requests();
+    assert_eq!(
+        failed_requests,
+        vec!["enable_app".to_string(), "enable_clone_cell".to_string()]
+    );
+}
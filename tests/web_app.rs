@@ -0,0 +1,159 @@
+#![cfg(feature = "web_app")]
+
+use holochain::prelude::{WebAppBundle, WebAppManifest};
+use holochain::sweettest::SweetConductor;
+use holochain_client::{AdminWebsocket, ConductorApiError, InstallWebAppPayload};
+use std::io::{Cursor, Write};
+use std::net::Ipv4Addr;
+use std::path::PathBuf;
+
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "holochain_client_web_app_{}_{}",
+        name,
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    dir
+}
+
+fn ui_zip(files: &[(&str, &str)]) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, contents) in files {
+        zip.start_file(*name, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        zip.write_all(contents.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+fn web_app_bundle(ui_zip: Vec<u8>) -> Vec<u8> {
+    let manifest: WebAppManifest = serde_yaml::from_str(
+        r#"
+manifest_version: "1"
+name: test-web-app
+ui:
+  bundled: ui.zip
+happ_manifest:
+  bundled: test.happ
+"#,
+    )
+    .unwrap();
+    let happ = std::fs::read("./fixture/test.happ").unwrap();
+
+    WebAppBundle::new(
+        manifest,
+        vec![
+            (PathBuf::from("ui.zip"), ui_zip.into()),
+            (PathBuf::from("test.happ"), happ.into()),
+        ],
+        PathBuf::from("."),
+    )
+    .unwrap()
+    .encode()
+    .unwrap()
+}
+
+async fn connect_admin(conductor: &SweetConductor) -> AdminWebsocket {
+    let admin_port = conductor.get_arbitrary_admin_websocket_port().unwrap();
+    AdminWebsocket::connect((Ipv4Addr::LOCALHOST, admin_port))
+        .await
+        .unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn install_web_app() {
+    let conductor = SweetConductor::from_standard_config().await;
+    let admin_ws = connect_admin(&conductor).await;
+
+    let ui_dir = test_dir("install");
+    let installed = admin_ws
+        .install_web_app(InstallWebAppPayload {
+            bundle: web_app_bundle(ui_zip(&[
+                ("index.html", "<html></html>"),
+                ("assets/app.js", "console.log('foo')"),
+            ])),
+            ui_dir: ui_dir.clone(),
+            agent_key: None,
+            installed_app_id: Some("test-web-app".into()),
+            network_seed: None,
+            roles_settings: None,
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(installed.app_info.installed_app_id, "test-web-app");
+    assert_eq!(installed.ui_dir, ui_dir);
+    assert_eq!(
+        std::fs::read_to_string(ui_dir.join("index.html")).unwrap(),
+        "<html></html>"
+    );
+    assert_eq!(
+        std::fs::read_to_string(ui_dir.join("assets/app.js")).unwrap(),
+        "console.log('foo')"
+    );
+
+    std::fs::remove_dir_all(ui_dir).unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn web_app_ui_path_traversal_is_rejected() {
+    let conductor = SweetConductor::from_standard_config().await;
+    let admin_ws = connect_admin(&conductor).await;
+
+    let ui_dir = test_dir("traversal");
+    let err = admin_ws
+        .install_web_app(InstallWebAppPayload {
+            bundle: web_app_bundle(ui_zip(&[
+                ("index.html", "<html></html>"),
+                ("../escaped.txt", "outside"),
+            ])),
+            ui_dir: ui_dir.clone(),
+            agent_key: None,
+            installed_app_id: Some("test-web-app".into()),
+            network_seed: None,
+            roles_settings: None,
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::BundleError(_)));
+
+    // Nothing was extracted or installed
+    assert!(!ui_dir.exists());
+    assert!(!ui_dir.parent().unwrap().join("escaped.txt").exists());
+    assert!(admin_ws.list_apps(None).await.unwrap().is_empty());
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn web_app_ui_over_size_limit_is_rejected() {
+    let conductor = SweetConductor::from_standard_config().await;
+    let admin_ws = connect_admin(&conductor).await;
+
+    // Zeros compress well, so the zip stays small while the extracted UI exceeds 512 MiB
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    zip.start_file("index.html", zip::write::SimpleFileOptions::default())
+        .unwrap();
+    let chunk = vec![0u8; 1024 * 1024];
+    for _ in 0..=512 {
+        zip.write_all(&chunk).unwrap();
+    }
+    let ui_zip = zip.finish().unwrap().into_inner();
+
+    let ui_dir = test_dir("over_size_limit");
+    let err = admin_ws
+        .install_web_app(InstallWebAppPayload {
+            bundle: web_app_bundle(ui_zip),
+            ui_dir: ui_dir.clone(),
+            agent_key: None,
+            installed_app_id: Some("test-web-app".into()),
+            network_seed: None,
+            roles_settings: None,
+        })
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConductorApiError::BundleError(_)));
+
+    // The partly extracted UI was removed and nothing was installed
+    assert!(!ui_dir.exists());
+    assert!(admin_ws.list_apps(None).await.unwrap().is_empty());
+}